@@ -1,5 +1,6 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
 
 const PI: f32 = 3.14159265359;
 
@@ -7,35 +8,6 @@ fn gaussian(sigma: f32, pos: f32)->f32{
     return (1. / sqrt(2. * PI * sigma * sigma)) * exp(-(pos * pos) / (2. * sigma * sigma));
 }
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
-
-
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
@@ -46,8 +18,8 @@ struct DoGSettings {
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let texel_size = vec2(x,y);
     let kernel_size = config.sigma_a * 2.0;
     var G = textureSample(screen_texture, texture_sampler, in.uv);
@@ -56,7 +28,7 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var st0 = vec2(in.uv);
     var v0 = v;
     for (var d = 1.0; d < kernel_size; d += 1.0) {
-        st0 += v0 * f32(config.edge_smooth_step_sizes.x);
+        st0 += v0 * config.edge_smooth_step_sizes.x;
         let c = textureSample(screen_texture,texture_sampler, st0);
         let gauss1 = gaussian(config.sigma_a, d);
 
@@ -70,7 +42,7 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var v1 = v;
 
     for (var d = 1.0; d < kernel_size; d += 1.0) {
-        st1 -= v1 * f32(config.edge_smooth_step_sizes.y);
+        st1 -= v1 * config.edge_smooth_step_sizes.y;
         let c = textureSample(screen_texture, texture_sampler, st1);
         let gauss1 = gaussian(config.sigma_a, d);
 
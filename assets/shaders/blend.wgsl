@@ -1,48 +1,223 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
 
 const PI: f32 = 3.14159265359;
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
 @group(0) @binding(3) var<uniform> config: DoGSettings;
 
 @group(1) @binding(0) var dog_texture: texture_2d<f32>;
-@group(1) @binding(1) var hatch_texture: texture_2d<f32>;
-@group(1) @binding(2) var hatch_sampler: sampler;
+// one hatching texture per layer
+@group(1) @binding(1) var hatch_texture_1: texture_2d<f32>;
+@group(1) @binding(2) var hatch_texture_2: texture_2d<f32>;
+@group(1) @binding(3) var hatch_texture_3: texture_2d<f32>;
+@group(1) @binding(4) var hatch_texture_4: texture_2d<f32>;
+@group(1) @binding(5) var hatch_sampler: sampler;
+
+// how fast the weight of a low resolution texel falls off with its difference in luminance
+const GUIDE_SIGMA: f32 = 0.1;
+
+fn luminance(color: vec3f) -> f32 {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// Joint bilateral upsampling of the dog texture, when it was computed at a lower resolution.
+// The bilinear weights of the four nearest texels are scaled by how similar the luminance the
+// texel was computed from is to the luminance of this pixel, so lines don't bleed over edges.
+fn upsample_dog(uv: vec2f, guide: f32) -> vec4f {
+    let size = vec2i(textureDimensions(dog_texture));
+    let position = uv * vec2f(size) - 0.5;
+    let base = vec2i(floor(position));
+    let f = fract(position);
+
+    var D = vec4(0.0);
+    var weight_sum = 0.0;
+    for (var i = 0; i < 4; i++) {
+        let offset = vec2(i % 2, i / 2);
+        let coords = clamp(base + offset, vec2i(0), size - 1);
+        let bilinear = mix(1.0 - f, f, vec2f(offset));
+        // the full resolution texture sampled where the lab pass sampled it for this texel
+        let texel_uv = (vec2f(coords) + 0.5) / vec2f(size);
+        let difference = luminance(textureSampleLevel(screen_texture, texture_sampler, texel_uv, 0.0).rgb) - guide;
+        let similarity = exp(-(difference * difference) / (2.0 * GUIDE_SIGMA * GUIDE_SIGMA));
+        // falls back to bilinear filtering, if none of the texels is similar
+        let weight = bilinear.x * bilinear.y * (similarity + 1e-4);
+
+        D += textureLoad(dog_texture, coords, 0) * weight;
+        weight_sum += weight;
+    }
+    return D / weight_sum;
+}
+
+#ifdef WORLD_SPACE_HATCHING
+#ifdef MULTISAMPLED
+@group(2) @binding(0) var depth_texture: texture_depth_multisampled_2d;
+@group(2) @binding(1) var normal_texture: texture_multisampled_2d<f32>;
+#else
+@group(2) @binding(0) var depth_texture: texture_depth_2d;
+@group(2) @binding(1) var normal_texture: texture_2d<f32>;
+#endif
+
+// The surface seen through a pixel, from the depth and normal prepass.
+struct Surface {
+    position: vec3f,
+    // how far the position moves to the next pixel to the right and below, on the same surface
+    position_dx: vec3f,
+    position_dy: vec3f,
+    // how much the planes facing the x, y and z axis contribute to the triplanar mapping
+    weights: vec3f,
+    // the background has no surface and is hatched in screen space
+    found: bool,
+}
+
+var<private> surface: Surface;
+
+// The world position of the surface at `coords` in xyz and its depth in w.
+fn surface_position(coords: vec2i) -> vec4f {
+    let size = vec2i(textureDimensions(depth_texture));
+    let clamped = clamp(coords, vec2i(0), size - 1);
+    let depth = textureLoad(depth_texture, clamped, 0);
+    let uv = (vec2f(clamped) + 0.5) / vec2f(size);
+    let world = view.world_from_clip * vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    return vec4(world.xyz / world.w, depth);
+}
+
+// How far the position moves to the next pixel along `step`. The implicit derivatives of the
+// position jump across silhouettes, where the neighbouring pixel sees another surface, so the
+// difference is taken to the neighbour whose depth is closer, which is on the same surface.
+fn surface_derivative(center: vec4f, coords: vec2i, step: vec2i) -> vec3f {
+    let next = surface_position(coords + step);
+    let previous = surface_position(coords - step);
+    var difference = center.xyz - previous.xyz;
+    var neighbour = previous.w;
+    if abs(next.w - center.w) < abs(previous.w - center.w) {
+        difference = next.xyz - center.xyz;
+        neighbour = next.w;
+    }
+    // a pixel surrounded by the background has no neighbour on its surface
+    return select(difference, vec3(0.0), neighbour <= 0.0);
+}
+
+fn load_surface(uv: vec2f) -> Surface {
+    let coords = vec2i(uv * vec2f(textureDimensions(depth_texture)));
+    let center = surface_position(coords);
+    let normal = normalize(textureLoad(normal_texture, coords, 0).xyz * 2.0 - 1.0);
+    // sharpened, so the planes only mix where the surface turns
+    let weights = pow(abs(normal), vec3(4.0));
+
+    var result: Surface;
+    result.position = center.xyz;
+    result.position_dx = surface_derivative(center, coords, vec2(1, 0));
+    result.position_dy = surface_derivative(center, coords, vec2(0, 1));
+    result.weights = weights / (weights.x + weights.y + weights.z);
+    result.found = center.w > 0.0;
+    return result;
+}
+#endif
+
+// the procedural strokes are laid out like on a texture of this size
+const PROCEDURAL_HATCH_SIZE: f32 = 512.0;
+
+// A pseudo random number in [0, 1) for every stroke.
+fn hash(n: f32) -> f32 {
+    return fract(sin(n * 12.9898) * 43758.5453);
+}
+
+// Procedural strokes along the x axis, `texel` is the position in texels of the virtual texture
+// and `footprint` how many of them a pixel covers across the strokes.
+// Like on the hatching textures, the paper is 1 and the strokes are 0.
+fn procedural_hatch(texel: vec2f, footprint: f32, layer: i32) -> f32 {
+    let spacing = max(config.hatch_spacings[layer], 1e-3);
+    let half_thickness = config.hatch_thicknesses[layer] * 0.5;
+    let row = texel.y / spacing;
+
+    // the strokes can be shifted into the neighbouring rows, so those are checked as well
+    var distance = 1e6;
+    for (var offset = -1.0; offset <= 1.0; offset += 1.0) {
+        let stroke = floor(row) + offset;
+        let seed = hash(stroke + f32(layer) * 101.0);
+        // every stroke is shifted by its own amount and bends along its length
+        let center = stroke + 0.5
+            + (seed - 0.5) * config.hatch_jitters[layer]
+            + sin((texel.x / (spacing * 8.0) + seed) * 2.0 * PI) * config.hatch_wobbles[layer];
+        distance = min(distance, abs(row - center) * spacing);
+    }
+
+    // antialiased over the footprint of the pixel
+    let half_footprint = max(footprint, 1e-4) * 0.5;
+    let coverage = 1.0 - smoothstep(half_thickness - half_footprint, half_thickness + half_footprint, distance);
+    // strokes that are closer together than a pixel fade to their average coverage instead of
+    // aliasing
+    let average = saturate(half_thickness * 2.0 / spacing);
+    let fade = saturate(footprint / spacing * 2.0 - 1.0);
+    return 1.0 - mix(coverage, average, fade);
+}
+
+// The size of the hatching of a layer in texels.
+fn hatch_size(hatch_texture: texture_2d<f32>) -> vec2f {
+    if config.procedural_hatch == 1 {
+        return vec2(PROCEDURAL_HATCH_SIZE);
+    }
+    return vec2f(textureDimensions(hatch_texture));
+}
+
+// The hatching of a layer at `uv`, where 1 is one repeat of the texture. `uv_dx` and `uv_dy`
+// are how far `uv` moves to the next pixel, which pick the mip level and the footprint of the
+// procedural strokes.
+fn hatch_at(hatch_texture: texture_2d<f32>, uv: vec2f, uv_dx: vec2f, uv_dy: vec2f, layer: i32) -> vec3f {
+    if config.procedural_hatch == 1 {
+        let texel = uv * PROCEDURAL_HATCH_SIZE;
+        let footprint = length(vec2(uv_dx.y, uv_dy.y)) * PROCEDURAL_HATCH_SIZE;
+        return vec3(procedural_hatch(texel, footprint, layer));
+    }
+    return textureSampleGrad(hatch_texture, hatch_sampler, uv, uv_dx, uv_dy).rgb;
+}
+
+// Samples the hatching of a layer rotated by `rotation` degrees. `pixel` is the position in
+// logical pixels from the center of the screen and `resolution` the texels per logical pixel.
+// Surfaces hatched in world space repeat the texture `world_hatch_scale` times per world unit.
+fn sample_hatch(hatch_texture: texture_2d<f32>, pixel: vec2f, rotation: f32, resolution: f32, layer: i32) -> vec3f {
+    let radians = rotation * PI / 180.0;
+    let rot: mat2x2<f32> = mat2x2<f32>(
+        cos(radians), -sin(radians), 
+        sin(radians), cos(radians), 
+    );
+    let screen_uv = rot * pixel * resolution / hatch_size(hatch_texture) + 0.5;
+    let screen = hatch_at(hatch_texture, screen_uv, dpdx(screen_uv), dpdy(screen_uv), layer);
+#ifdef WORLD_SPACE_HATCHING
+    // triplanar mapping, the hatching is projected along every axis onto the planes facing it
+    let scale = config.world_hatch_scale;
+    let position = surface.position * scale;
+    let dx = surface.position_dx * scale;
+    let dy = surface.position_dy * scale;
+    let x = hatch_at(hatch_texture, rot * position.zy, rot * dx.zy, rot * dy.zy, layer);
+    let y = hatch_at(hatch_texture, rot * position.xz, rot * dx.xz, rot * dy.xz, layer);
+    let z = hatch_at(hatch_texture, rot * position.xy, rot * dx.xy, rot * dy.xy, layer);
+    let world = x * surface.weights.x + y * surface.weights.y + z * surface.weights.z;
+    return select(screen, world, surface.found);
+#else
+    return screen;
+#endif
+}
+
+#ifdef TONAL_ART_MAP
+// One tone of the tonal art map, which covers the lighter tones by `coverage`.
+fn hatch_tone(hatch_texture: texture_2d<f32>, pixel: vec2f, rotation: f32, resolution: f32, layer: i32, coverage: f32) -> vec3f {
+    return mix(vec3(1.0), sample_hatch(hatch_texture, pixel, rotation, resolution, layer), saturate(coverage));
+}
+#endif
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
     let main = textureSample(screen_texture, texture_sampler, in.uv);
-    let D = textureSample(dog_texture, texture_sampler, in.uv) * config.dog_strength;
+    var D = textureSample(dog_texture, texture_sampler, in.uv);
+    if any(textureDimensions(dog_texture) != textureDimensions(screen_texture)) {
+        D = upsample_dog(in.uv, luminance(main.rgb));
+    }
+    D *= config.dog_strength;
     let col = main.rgb;
     var output = vec3(0.);
 
@@ -61,47 +236,58 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
     // this uses max color, but I could also mix with min color
     if config.enable_hatch == 1 {
         output = vec3(1.0);
-        let hatchUV = in.uv * 2.0 -1.0; 
+        // logical pixels from the center of the screen, so the strokes keep their size and aspect
+        // ratio on every window
+        let hatchUV = (in.uv - 0.5) * vec2f(textureDimensions(screen_texture)) / config.scale_factor;
+#ifdef WORLD_SPACE_HATCHING
+        surface = load_surface(in.uv);
+#endif
+#ifdef TONAL_ART_MAP
+        // the tones fade in one after the other from light to dark, each on top of the lighter
+        // ones, so darker regions get denser strokes
+        // colors brighter than white, e.g. on hdr cameras, get no strokes from any tone
+        let darkness = (1.0 - max(luminance(col), 0.0)) * 4.0;
+        let rotations = config.hatch_rotations;
+        let resolutions = config.hatch_resolutions;
+        let tone = hatch_tone(hatch_texture_1, hatchUV, rotations.r, resolutions.x, 0, darkness)
+            * hatch_tone(hatch_texture_2, hatchUV, rotations.g, resolutions.y, 1, darkness - 1.0)
+            * hatch_tone(hatch_texture_3, hatchUV, rotations.b, resolutions.z, 2, darkness - 2.0)
+            * hatch_tone(hatch_texture_4, hatchUV, rotations.a, resolutions.w, 3, darkness - 3.0);
+        output = mix(config.min_color, config.max_color, tone);
+        // the lines of every enabled layer are drawn on top of the tone
+        if config.enable_layers.x == 1.0 {
+            output = vec3(mix(config.min_color, config.max_color, D.r)) * output.rgb;
+        }
+        if  config.enable_layers.y != 0.0 {
+            output = vec3(mix(config.min_color, config.max_color, D.g)) * output.rgb;
+        }
+        if  config.enable_layers.z != 0.0 {
+            output = vec3(mix(config.min_color, config.max_color, D.b)) * output.rgb;
+        }
+        if  config.enable_layers.w != 0.0 {
+            output = vec3(mix(config.min_color, config.max_color, D.a)) * output.rgb;
+        }
+#else
         if config.enable_layers.x == 1.0 {
-            let radians = config.hatch_rotations.r * PI / 180.0;
-            let rot: mat2x2<f32> = mat2x2<f32>(
-                cos(radians), -sin(radians), 
-                sin(radians), cos(radians), 
-            );
-
-            let s1 = textureSample(
-                hatch_texture, hatch_sampler, (hatchUV * rot) * config.hatch_resolutions.x * 0.5 + 0.5).rgb;
+            // the first layer is rotated the other way
+            let s1 = sample_hatch(hatch_texture_1, hatchUV, -config.hatch_rotations.r, config.hatch_resolutions.x, 0);
             output = vec3(mix(s1, config.max_color, D.r));
         }
 
         // every enabled layer will just add the respective hatching texture to the output
         if  config.enable_layers.y != 0.0 {
-            let radians = config.hatch_rotations.g * PI / 180.0;
-            let rot: mat2x2<f32> = mat2x2<f32>(
-                cos(radians), -sin(radians), 
-                sin(radians), cos(radians), 
-            );
-            let s2 = textureSample(hatch_texture, hatch_sampler, rot * hatchUV * config.hatch_resolutions.y * 0.5 + 0.5).rgb;
+            let s2 = sample_hatch(hatch_texture_2, hatchUV, config.hatch_rotations.g, config.hatch_resolutions.y, 1);
             output = vec3(mix(s2, config.max_color, D.g)) * output.rgb;
         }
         if  config.enable_layers.z != 0.0 {
-            let radians = config.hatch_rotations.b * PI / 180.0;
-            let rot: mat2x2<f32> = mat2x2<f32>(
-                cos(radians), -sin(radians), 
-                sin(radians), cos(radians), 
-            );
-            let s3 = textureSample(hatch_texture, hatch_sampler, rot * hatchUV * config.hatch_resolutions.z * 0.5 + 0.5).rgb;
+            let s3 = sample_hatch(hatch_texture_3, hatchUV, config.hatch_rotations.b, config.hatch_resolutions.z, 2);
             output = vec3(mix(s3, config.max_color, D.b)) * output.rgb;
         }
         if  config.enable_layers.w != 0.0 {
-            let radians = config.hatch_rotations.a * PI / 180.0;
-            let rot: mat2x2<f32> = mat2x2<f32>(
-                cos(radians), -sin(radians), 
-                sin(radians), cos(radians), 
-            );
-            let s4 = textureSample(hatch_texture, hatch_sampler, rot * hatchUV * config.hatch_resolutions.w * 0.5 + 0.5).rgb;
+            let s4 = sample_hatch(hatch_texture_4, hatchUV, config.hatch_rotations.a, config.hatch_resolutions.w, 3);
             output = vec3(mix(s4, config.max_color, D.a)) * output.rgb;
         }
+#endif
     } else {
         output = vec3(1.0);
         if config.enable_layers.x == 1.0 {
@@ -120,7 +306,12 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
 
     }
 
-    return saturate(vec4(mix(col, output, config.blend_strength), 1.0));
-    // return saturate(vec4(mix(col, output, 0.0), 1.0));
+    let blended = vec4(mix(col, output, config.blend_strength), 1.0);
+#ifdef HDR
+    // the tonemapping after the node maps the values above 1
+    return max(blended, vec4(0.0));
+#else
+    return saturate(blended);
+#endif
 }
 
@@ -1,5 +1,7 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
+#import bevy_dog::threshold::threshold
 
 const PI: f32 = 3.14159265359;
 
@@ -7,52 +9,35 @@ fn gaussian(sigma: f32, pos: f32)->f32{
     return (1. / sqrt(2. * PI * sigma * sigma)) * exp(-(pos * pos) / (2. * sigma * sigma));
 }
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
 @group(0) @binding(3) var<uniform> config: DoGSettings;
 
-@group(1) @binding(0) var tfm_texture: texture_2d<f32>;
-@group(1) @binding(1) var point_clamp_sampler: sampler;
+#ifdef COMPUTE
+#import bevy_dog::gaussian_compute::{load_tile, tile_texel, MAX_RADIUS, WORKGROUP_SIZE}
 
+#ifdef MASK_OUTPUT
+@group(1) @binding(0) var output_texture: texture_storage_2d<rgba8unorm, write>;
+#else
+@group(1) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
+#endif
+#endif
+
+fn dog_kernel_size() -> f32 {
+    return select(2.0, floor(config.sigma_e * 2.), config.sigma_e * 2. > 2.);
+}
 
 @fragment
 fn first_gaussian_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let texel_size = vec2(x,y);
     var col = vec2(0.0);
     var kernel_sum1 = 0.0;
     var kernel_sum2 = 0.0;
 
-    let kernel_size = select(2.0, floor(config.sigma_e * 2.), config.sigma_e *2. > 2.);
+    let kernel_size = dog_kernel_size();
 
     for (var x = -kernel_size; x <= kernel_size; x += 1.0) {
         let c = textureSample(screen_texture, texture_sampler, in.uv + vec2(x, 0.) * texel_size.xy).r;
@@ -71,14 +56,14 @@ fn first_gaussian_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
 
 @fragment
 fn second_gaussian_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let texel_size = vec2(x,y);
     var col = vec2(0.);
     var kernel_sum1 = 0.0;
     var kernel_sum2 = 0.0;
 
-    let kernel_size = select(2.0, floor(config.sigma_e * 2.), (config.sigma_e * 2. > 2.));
+    let kernel_size = dog_kernel_size();
 
     for (var y = -kernel_size; y <= kernel_size; y+= 1.0) {
         let c = textureSample(screen_texture, texture_sampler, in.uv + vec2(0., y) * texel_size.xy).rg;
@@ -96,39 +81,66 @@ fn second_gaussian_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
 
     let D = (1. + config.tau) * (G.r * 100.0) - config.tau * (G.g * 100.0);
 
-    var output = vec4(0.0);
-
-    if (config.thresholding == 1) {
-        output.r = select(1+tanh(config.phi * (D-config.thresholds.x)),1.0,D>= config.thresholds.x);
-        output.g = select(1+tanh(config.phi * (D-config.thresholds.y)),1.0,D>=config.thresholds.y);
-        output.b = select(1+tanh(config.phi * (D-config.thresholds.z)),1.0,D>=config.thresholds.z);
-        output.a = select(1+tanh(config.phi * (D-config.thresholds.w)),1.0,D>=config.thresholds.w);
-    } else if (config.thresholding == 2) {
-        let a = 1.0 / config.quantizer_step;
-        let b = config.thresholds.x / 100.0;
-        let x = D / 100.0;
-
-        // TODO this was not vec4 in the original code
-        output = vec4(select(a* floor((pow(x,config.phi) - (a*b/2.)) / (a*b)+0.5), 1.0, x>= b));
-    } else if (config.thresholding == 2) {
-        let a = 1.0 / config.quantizer_step;
-        let b = config.thresholds.x / 100.0;
-        let x = D / 100.0;
-
-        output = vec4(select(a* floor((pow(x,config.phi) - (a*b/2.)) / (a*b)+0.5), 1.0, x>= b));
-    } else if (config.thresholding == 3) {
-        let x = D / 100.;
-        let qn = floor(x * config.quantizer_step + 0.5) / config.quantizer_step;
-        let qs = smoothstep(-2.0, 2.0, config.phi * (x - qn) * 10.0) - 0.5;
-        output = vec4(qn + qs / config.quantizer_step);
-    } else {
-        output = vec4(D / 100.0);
+    return threshold(D, config);
+}
+
+#ifdef COMPUTE
+@compute @workgroup_size(128, 1, 1)
+fn first_gaussian_blur_compute(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let origin = vec2i(i32(workgroup_id.x * WORKGROUP_SIZE), i32(workgroup_id.y));
+    load_tile(screen_texture, origin, vec2(1, 0), local_index);
+
+    let coords = origin + vec2(i32(local_index), 0);
+    if any(coords >= vec2i(textureDimensions(output_texture))) {
+        return;
     }
 
-    if config.invert == 1 {
-        output = 1.0 - output;
+    let kernel_size = min(i32(dog_kernel_size()), MAX_RADIUS);
+    var col = vec2(0.0);
+    var kernel_sum = vec2(0.0);
+
+    for (var x = -kernel_size; x <= kernel_size; x++) {
+        let c = tile_texel(local_index, x).r;
+        let gauss = vec2(gaussian(config.sigma_e, f32(x)), gaussian(config.sigma_e * config.k, f32(x)));
+
+        col += c * gauss;
+        kernel_sum += gauss;
     }
 
-    return saturate(output);
+    textureStore(output_texture, coords, vec4(col / kernel_sum, 0., 0.));
 }
 
+@compute @workgroup_size(1, 128, 1)
+fn second_gaussian_blur_compute(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let origin = vec2i(i32(workgroup_id.x), i32(workgroup_id.y * WORKGROUP_SIZE));
+    load_tile(screen_texture, origin, vec2(0, 1), local_index);
+
+    let coords = origin + vec2(0, i32(local_index));
+    if any(coords >= vec2i(textureDimensions(output_texture))) {
+        return;
+    }
+
+    let kernel_size = min(i32(dog_kernel_size()), MAX_RADIUS);
+    var col = vec2(0.0);
+    var kernel_sum = vec2(0.0);
+
+    for (var y = -kernel_size; y <= kernel_size; y++) {
+        let c = tile_texel(local_index, y).rg;
+        let gauss = vec2(gaussian(config.sigma_e, f32(y)), gaussian(config.sigma_e * config.k, f32(y)));
+
+        col += c * gauss;
+        kernel_sum += gauss;
+    }
+
+    let G = col / kernel_sum;
+    let D = (1. + config.tau) * (G.r * 100.0) - config.tau * (G.g * 100.0);
+
+    textureStore(output_texture, coords, threshold(D, config));
+}
+#endif
@@ -1,5 +1,7 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
+#import bevy_dog::threshold::threshold
 
 const PI: f32 = 3.14159265359;
 
@@ -7,33 +9,6 @@ fn gaussian(sigma: f32, pos: f32)->f32{
     return (1. / sqrt(2. * PI * sigma * sigma)) * exp(-(pos * pos) / (2. * sigma * sigma));
 }
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
@@ -42,11 +17,18 @@ struct DoGSettings {
 @group(1) @binding(0) var tfm_texture: texture_2d<f32>;
 @group(1) @binding(1) var point_clamp_sampler: sampler;
 
+#ifdef COMPUTE
+@group(2) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
+#endif
+
+fn gradient_kernel_size() -> f32 {
+    return select(1.0, floor(config.sigma_e * 2.0), config.sigma_e * 2.0 > 1.0);
+}
 
 @fragment
 fn fdog_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let xy = vec2(x,y);
     let tfm = textureSample(tfm_texture, point_clamp_sampler, in.uv); 
     // direction of the vector flow
@@ -58,7 +40,7 @@ fn fdog_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
     n *= xy;
     var col = textureSample(screen_texture, texture_sampler, in.uv).xx; // x = lightness in lab
     var kernel_sum = vec2(1.0);
-    let kernel_size = select(1.0, floor(config.sigma_e * 2.0), config.sigma_e * 2.0 > 1.0);
+    let kernel_size = gradient_kernel_size();
 
     for (var x = ds; x <= kernel_size; x += 1.0){
         let gauss1 = gaussian(config.sigma_e, x);
@@ -83,8 +65,8 @@ fn fdog_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
 
 @fragment
 fn fdog_blur_and_difference(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let xy = vec2(x,y);
     let kernel_size = config.sigma_m * 2.0;
 
@@ -96,7 +78,7 @@ fn fdog_blur_and_difference(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var v0 = tfm_frag;
 
     for (var d = 1.0; d < kernel_size; d += 1.0) {
-        st0 += v0 * vec2(f32(config.line_conv_step_sizes.x));
+        st0 += v0 * config.line_conv_step_sizes.x;
         c = textureSample(screen_texture, texture_sampler, st0).rgb;
         let gauss1 = gaussian(config.sigma_m, d);
 
@@ -120,13 +102,12 @@ fn fdog_blur_and_difference(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var v1 = tfm_frag;
 
     for (var d = 1.0; d < kernel_size; d+=1.0) {
-        st1 -= v1 * f32(config.line_conv_step_sizes.y);
+        st1 -= v1 * config.line_conv_step_sizes.y;
         let c = textureSample(screen_texture, texture_sampler, st1).rgb;
         let gauss1 = gaussian(config.sigma_m, d);
 
 
         if (config.calc_diff_before_convolution != 0) {
-            G.r += gauss1 * c.b;
             G.r += gauss1 * c.b;
             w.x += gauss1;
         } else {
@@ -151,32 +132,87 @@ fn fdog_blur_and_difference(in: FullscreenVertexOutput) -> @location(0) vec4f {
         D = (1 + config.tau) * (G.r * 100.0) - config.tau * (G.g * 100.0);
     }
 
-    var output = vec4(0.0);
-
-    if (config.thresholding == 1) {
-        output.r = select(1+tanh(config.phi * (D-config.thresholds.x)),1.0,D>=config.thresholds.x);
-        output.g = select(1+tanh(config.phi * (D-config.thresholds.y)),1.0,D>=config.thresholds.y);
-        output.b = select(1+tanh(config.phi * (D-config.thresholds.z)),1.0,D>=config.thresholds.z);
-        output.a = select(1+tanh(config.phi * (D-config.thresholds.w)),1.0,D>=config.thresholds.w);
-    } else if (config.thresholding == 2) {
-        let a = 1.0 / config.quantizer_step;
-        let b = config.thresholds.x / 100.0;
-        let x = D / 100.0;
-
-        output = vec4(select(a* floor((pow(x,config.phi) - (a*b/2.)) / (a*b)+0.5), 1.0, x>= b));
-    } else if (config.thresholding == 3) {
-        let x = D / 100.;
-        let qn = floor(x * config.quantizer_step + 0.5) / config.quantizer_step;
-        let qs = smoothstep(-2.0, 2.0, config.phi * (x - qn) * 10.0) - 0.5;
-        output = vec4(qn + qs / config.quantizer_step);
-    } else {
-        output = vec4(D / 100.0);
+    return threshold(D, config);
+}
+
+#ifdef COMPUTE
+// The gradient pass samples along the gradient in every direction, so it needs a square tile.
+// The flow pass (fdog_blur_and_difference) follows the streamlines of the flow field, which can
+// leave any tile, so it always runs as a fragment pass.
+const TILE_SIZE: i32 = 16;
+// kernels with a larger radius get clamped to this on the compute path
+const MAX_GRADIENT_RADIUS: f32 = 16.0;
+// one more texel than the radius for the bilinear taps
+const APRON: i32 = 17;
+const TILE_SIDE: i32 = 50; // TILE_SIZE + 2 * APRON
+
+var<workgroup> lightness_tile: array<f32, 2500>;
+
+fn tile_index(texel: vec2i) -> i32 {
+    return texel.y * TILE_SIDE + texel.x;
+}
+
+// bilinear filtering of the tile, like the linear sampler of the fragment pass
+fn sample_tile(position: vec2f) -> f32 {
+    let p = position - 0.5;
+    let texel = vec2i(floor(p));
+    let f = fract(p);
+
+    let top = mix(lightness_tile[tile_index(texel)], lightness_tile[tile_index(texel + vec2(1, 0))], f.x);
+    let bottom = mix(lightness_tile[tile_index(texel + vec2(0, 1))], lightness_tile[tile_index(texel + vec2(1, 1))], f.x);
+    return mix(top, bottom, f.y);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn fdog_blur_compute(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_id) local_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let size = vec2i(textureDimensions(screen_texture));
+    let origin = vec2i(workgroup_id.xy) * TILE_SIZE - APRON;
+
+    for (var i = i32(local_index); i < TILE_SIDE * TILE_SIDE; i += TILE_SIZE * TILE_SIZE) {
+        let coords = clamp(origin + vec2(i % TILE_SIDE, i / TILE_SIDE), vec2i(0), size - 1);
+        lightness_tile[i] = textureLoad(screen_texture, coords, 0).r;
     }
+    workgroupBarrier();
 
-    if config.invert == 1 {
-        output = 1.0 - output;
+    let coords = vec2i(workgroup_id.xy) * TILE_SIZE + vec2i(local_id.xy);
+    if any(coords >= size) {
+        return;
     }
 
-    return saturate(output);
-}
+    // direction of the vector flow
+    let tensor = textureLoad(tfm_texture, coords, 0).xy;
+    let n = vec2(tensor.y, -tensor.x);
+    let n_abs = abs(n);
+    let ds = 1.0 / (max(n_abs.x, n_abs.y));
+
+    let center = vec2f(coords - origin) + 0.5;
+    var col = vec2(lightness_tile[tile_index(coords - origin)]);
+    var kernel_sum = vec2(1.0);
+    let kernel_size = min(gradient_kernel_size(), MAX_GRADIENT_RADIUS);
+
+    for (var x = ds; x <= kernel_size; x += 1.0) {
+        let gauss1 = gaussian(config.sigma_e, x);
+        let gauss2 = gaussian(config.sigma_e * config.k, x);
+
+        let c1 = sample_tile(center - x * n);
+        let c2 = sample_tile(center + x * n);
 
+        col.r += (c1 + c2) * gauss1;
+        kernel_sum.x += 2.0 * gauss1;
+
+        col.g += (c1 + c2) * gauss2;
+        kernel_sum.y += 2.0 * gauss2;
+    }
+    col /= kernel_sum;
+
+    textureStore(
+        output_texture,
+        coords,
+        vec4(col, (1 + config.tau) * (col.r * 100.0) - config.tau * (col.g * 100.0), 1.0),
+    );
+}
+#endif
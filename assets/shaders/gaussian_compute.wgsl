@@ -0,0 +1,30 @@
+#define_import_path bevy_dog::gaussian_compute
+
+// Shared memory tiles for the compute versions of the separable gaussian passes.
+// Every workgroup handles one row (or column) segment of WORKGROUP_SIZE pixels and first loads it
+// together with an apron of MAX_RADIUS pixels on both sides, so every tap of the kernel is a
+// workgroup memory read instead of a texture fetch.
+
+const WORKGROUP_SIZE: u32 = 128u;
+// kernels with a larger radius get clamped to this on the compute path
+const MAX_RADIUS: i32 = 32;
+const TILE_LENGTH: u32 = 192u; // WORKGROUP_SIZE + 2 * MAX_RADIUS
+
+var<workgroup> tile: array<vec4f, TILE_LENGTH>;
+
+// `direction` is (1, 0) for a horizontal and (0, 1) for a vertical pass.
+// Has to be called from uniform control flow, because it synchronizes the workgroup.
+fn load_tile(source: texture_2d<f32>, origin: vec2i, direction: vec2i, local_index: u32) {
+    let max_coords = vec2i(textureDimensions(source)) - 1;
+    for (var i = local_index; i < TILE_LENGTH; i += WORKGROUP_SIZE) {
+        // clamping the coordinates is the same as the clamp to edge sampler of the fragment passes
+        let coords = clamp(origin + direction * (i32(i) - MAX_RADIUS), vec2i(0), max_coords);
+        tile[i] = textureLoad(source, coords, 0);
+    }
+    workgroupBarrier();
+}
+
+// The texel `offset` pixels away from the pixel of this invocation.
+fn tile_texel(local_index: u32, offset: i32) -> vec4f {
+    return tile[i32(local_index) + MAX_RADIUS + offset];
+}
@@ -1,5 +1,6 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
 
 fn luminance(color: vec3f) -> f32 {
     return dot(color, vec3(0.299f, 0.587f, 0.114f));
@@ -10,8 +11,8 @@ fn rgb2xyz(c: vec3f) -> vec3f{
     var tmp = vec3(0.);
 
     tmp.x = select(c.r/12.92, pow((c.r+0.055)/1.055, 2.4), c.r>0.04045);
-    tmp.x = select(c.g/12.92, pow((c.g+0.055)/1.055, 2.4), c.g>0.04045);
-    tmp.x = select(c.b/12.92, pow((c.b+0.055)/1.055, 2.4), c.b>0.04045);
+    tmp.y = select(c.g/12.92, pow((c.g+0.055)/1.055, 2.4), c.g>0.04045);
+    tmp.z = select(c.b/12.92, pow((c.b+0.055)/1.055, 2.4), c.b>0.04045);
     
     let mat: mat3x3<f32> = mat3x3<f32>(
         0.4124, 0.3576, 0.1805,
@@ -39,40 +40,34 @@ fn rgb2lab(c: vec3f) -> vec3f {
     return vec3(lab.x / 100.0f, 0.5 + 0.5 * (lab.y / 127.0), 0.5 + 0.5 * (lab.z / 127.0));
 }
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
 @group(0) @binding(3) var<uniform> config: DoGSettings;
 
+// At a reduced resolution scale a texel of the lab texture covers several pixels of the source.
+// They are averaged with bilinear taps two pixels apart, which each average 2x2 pixels, so thin
+// edges don't alias.
+const MAX_SOURCE_TAPS: f32 = 8.0;
+
+fn sample_source(uv: vec2f) -> vec3f {
+    let source_size = vec2f(textureDimensions(screen_texture));
+    let footprint = vec2(abs(dpdx(uv.x)), abs(dpdy(uv.y))) * source_size;
+    let taps = vec2u(clamp(ceil(footprint / 2.0), vec2(1.0), vec2(MAX_SOURCE_TAPS)));
+
+    var sum = vec3(0.);
+    for (var y = 0u; y < taps.y; y++) {
+        for (var x = 0u; x < taps.x; x++) {
+            let offset = ((vec2f(f32(x), f32(y)) + 0.5) / vec2f(taps) - 0.5) * footprint;
+            sum += textureSampleLevel(screen_texture, texture_sampler, uv + offset / source_size, 0.0).rgb;
+        }
+    }
+    return sum / f32(taps.x * taps.y);
+}
+
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let main = textureSample(screen_texture, texture_sampler, in.uv);
+    let main = sample_source(in.uv);
     let lab = rgb2lab(main.rgb);
     let lum = luminance(main.rgb);
     let out = vec3(lum);
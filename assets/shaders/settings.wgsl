@@ -0,0 +1,39 @@
+#define_import_path bevy_dog::settings
+
+// The layout of `DoGSettingsUniform` in `src/settings.rs`, shared by every pass.
+// The enums and bools of `DoGSettings` are packed into i32s, see the `Thresholding` and
+// `BlendMode` enums for the values.
+// `tests/settings_layout.rs` checks this struct against the Rust type, keep them in sync.
+struct DoGSettings {
+    thresholding: i32,
+    blend_mode: i32,
+    invert: i32,
+    calc_diff_before_convolution: i32,
+    sigma_c: f32,
+    sigma_e: f32,
+    sigma_m: f32,
+    sigma_a: f32,
+    quantizer_step: f32,
+    k: f32,
+    tau: f32,
+    phi: f32,
+    blend_strength: f32,
+    dog_strength: f32,
+    line_conv_step_sizes: vec2f,
+    edge_smooth_step_sizes: vec2f,
+    min_color: vec3f,
+    max_color: vec3f,
+    enable_hatch: i32,
+    enable_layers: vec4f,
+    hatch_resolutions: vec4f,
+    hatch_rotations: vec4f,
+    world_hatch_scale: f32,
+    procedural_hatch: i32,
+    hatch_spacings: vec4f,
+    hatch_thicknesses: vec4f,
+    hatch_jitters: vec4f,
+    hatch_wobbles: vec4f,
+    thresholds: vec4f,
+    // the scale factor of the render target, physical pixels per logical pixel
+    scale_factor: f32,
+}
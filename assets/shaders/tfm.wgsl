@@ -1,5 +1,6 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import bevy_render::view::View
+#import bevy_dog::settings::DoGSettings
 
 const PI: f32 = 3.14159265359;
 
@@ -7,45 +8,34 @@ fn gaussian(sigma: f32, pos: f32)->f32{
     return (1. / sqrt(2. * PI * sigma * sigma)) * exp(-(pos * pos) / (2. * sigma * sigma));
 }
 
-struct DoGSettings {
-    thresholding: i32,
-    blend_mode: i32,
-    invert: i32,
-    calc_diff_before_convolution: i32,
-    sigma_c: f32,
-    sigma_e: f32,
-    sigma_m: f32,
-    sigma_a: f32,
-    quantizer_step: f32,
-    k: f32,
-    tau: f32,
-    phi: f32,
-    blend_strength: f32,
-    dog_strength: f32,
-    line_conv_step_sizes: vec2i,
-    edge_smooth_step_sizes: vec2i,
-    min_color: vec3f,
-    max_color: vec3f,
-    enable_hatch: i32,
-    enable_layers: vec4f,
-    hatch_resolutions: vec4f,
-    hatch_rotations: vec4f,
-    thresholds: vec4f,
-}
-
-
-
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> view: View;
 @group(0) @binding(3) var<uniform> config: DoGSettings;
 
+#ifdef COMPUTE
+#import bevy_dog::gaussian_compute::{load_tile, tile_texel, MAX_RADIUS, WORKGROUP_SIZE}
+
+@group(1) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
+#endif
+
+fn tensor_kernel_radius() -> f32 {
+    return max(1.0, floor(config.sigma_c * 2.45));
+}
+
+// turns the blurred structure tensor into the flow direction and the edge strength
+fn flow_from_tensor(g: vec3f) -> vec4f {
+    let lambda1 = 0.5 * (g.y + g.x + sqrt(g.y * g.y - 2.0 * g.x * g.y + g.x * g.x + 4.0 * g.z * g.z));
+    let d = vec2(g.x - lambda1, g.z);
+
+    return select(vec4(0.,1.,0.,1.), vec4(normalize(d), sqrt(lambda1), 1.), length(d) != 0);
+}
 
 @fragment
 fn calculate_eigenvector(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var out = vec4(1.0);
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
 
     let Sx = vec3((
         1.0 * textureSample(screen_texture, texture_sampler, in.uv + vec2(-x, -y)).rgb +
@@ -75,10 +65,10 @@ fn calculate_eigenvector(in: FullscreenVertexOutput) -> @location(0) vec4f {
 @fragment
 fn horizontal_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
     var out = vec4(1.0);
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let xy = vec2(x,y);
-    let kernelRadius = max(1.0, floor(config.sigma_c * 2.45));
+    let kernelRadius = tensor_kernel_radius();
     var col = vec4<f32>(0.0);
     var kernelSum = 0.0;
     
@@ -101,12 +91,12 @@ fn horizontal_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
 fn vertical_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
     // var out = textureSample(screen_texture, texture_sampler, in.uv);
     var out = vec4(1.0);
-    let kernelRadius = max(1.0, floor(config.sigma_c * 2.45));
+    let kernelRadius = tensor_kernel_radius();
     var col = vec4<f32>(0.0);
     var kernelSum = 0.0;
     
-    let x = 1/view.viewport.z;
-    let y = 1/view.viewport.w;
+    let x = 1/f32(textureDimensions(screen_texture).x);
+    let y = 1/f32(textureDimensions(screen_texture).y);
     let xy = vec2(x,y);
     for (var y = -kernelRadius; y <= kernelRadius; y = y+1.) {
         let samplePos = in.uv + vec2(0.0, y) * xy;
@@ -117,10 +107,53 @@ fn vertical_blur_pass(in: FullscreenVertexOutput) -> @location(0) vec4f {
         kernelSum += gauss;
     }
 
-    let g = vec3(col.rgb / kernelSum);
-    let lambda1 = 0.5 * (g.y + g.x + sqrt(g.y * g.y - 2.0 * g.x * g.y + g.x * g.x + 4.0 * g.z * g.z));
-    let d = vec2(g.x - lambda1, g.z);
-    
-    out = select(vec4(0.,1.,0.,1.), vec4(normalize(d), sqrt(lambda1), 1.), length(d) != 0);
+    out = flow_from_tensor(col.rgb / kernelSum);
     return out;
 }
+
+#ifdef COMPUTE
+fn blur_tile(local_index: u32) -> vec4f {
+    let kernel_radius = min(i32(tensor_kernel_radius()), MAX_RADIUS);
+    var col = vec4(0.0);
+    var kernel_sum = 0.0;
+
+    for (var x = -kernel_radius; x <= kernel_radius; x++) {
+        let gauss = gaussian(config.sigma_c, f32(x));
+
+        col += tile_texel(local_index, x) * gauss;
+        kernel_sum += gauss;
+    }
+
+    return col / kernel_sum;
+}
+
+@compute @workgroup_size(128, 1, 1)
+fn horizontal_blur_compute(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let origin = vec2i(i32(workgroup_id.x * WORKGROUP_SIZE), i32(workgroup_id.y));
+    load_tile(screen_texture, origin, vec2(1, 0), local_index);
+
+    let coords = origin + vec2(i32(local_index), 0);
+    if any(coords >= vec2i(textureDimensions(output_texture))) {
+        return;
+    }
+    textureStore(output_texture, coords, blur_tile(local_index));
+}
+
+@compute @workgroup_size(1, 128, 1)
+fn vertical_blur_compute(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let origin = vec2i(i32(workgroup_id.x), i32(workgroup_id.y * WORKGROUP_SIZE));
+    load_tile(screen_texture, origin, vec2(0, 1), local_index);
+
+    let coords = origin + vec2(0, i32(local_index));
+    if any(coords >= vec2i(textureDimensions(output_texture))) {
+        return;
+    }
+    textureStore(output_texture, coords, flow_from_tensor(blur_tile(local_index).rgb));
+}
+#endif
@@ -0,0 +1,37 @@
+#define_import_path bevy_dog::threshold
+
+#import bevy_dog::settings::DoGSettings
+
+// The thresholding shared by the DoG and FDoG passes.
+
+// applies the thresholding to the difference of the two gaussians
+fn threshold(D: f32, config: DoGSettings) -> vec4f {
+    var output = vec4(0.0);
+
+    if (config.thresholding == 1) {
+        output.r = select(1+tanh(config.phi * (D-config.thresholds.x)),1.0,D>=config.thresholds.x);
+        output.g = select(1+tanh(config.phi * (D-config.thresholds.y)),1.0,D>=config.thresholds.y);
+        output.b = select(1+tanh(config.phi * (D-config.thresholds.z)),1.0,D>=config.thresholds.z);
+        output.a = select(1+tanh(config.phi * (D-config.thresholds.w)),1.0,D>=config.thresholds.w);
+    } else if (config.thresholding == 2) {
+        let a = 1.0 / config.quantizer_step;
+        let b = config.thresholds.x / 100.0;
+        let x = D / 100.0;
+
+        // TODO this was not vec4 in the original code
+        output = vec4(select(a* floor((pow(x,config.phi) - (a*b/2.)) / (a*b)+0.5), 1.0, x>= b));
+    } else if (config.thresholding == 3) {
+        let x = D / 100.;
+        let qn = floor(x * config.quantizer_step + 0.5) / config.quantizer_step;
+        let qs = smoothstep(-2.0, 2.0, config.phi * (x - qn) * 10.0) - 0.5;
+        output = vec4(qn + qs / config.quantizer_step);
+    } else {
+        output = vec4(D / 100.0);
+    }
+
+    if config.invert == 1 {
+        output = 1.0 - output;
+    }
+
+    return saturate(output);
+}
@@ -1,15 +1,16 @@
 use bevy::prelude::*;
 use bevy_dog::{
+    hatching::{DoGHatching, HatchingSpace},
     plugin::DoGPlugin,
     settings::{DoGSettings, PassesSettings},
 };
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, DoGPlugin))
+        .add_plugins((DefaultPlugins, DoGPlugin::default()))
         .register_type::<Rotates>()
         .add_systems(Startup, setup)
-        .add_systems(Update, (rotate, quit))
+        .add_systems(Update, (rotate, toggle_hatching_space, quit))
         .run();
 }
 
@@ -49,6 +50,7 @@ fn setup(
         Transform::from_translation(Vec3::new(5.0, 3.0, 0.0)).looking_at(Vec3::default(), Vec3::Y),
         DoGSettings::CROSSHATCH,
         PassesSettings::default(),
+        DoGHatching::default(),
     ));
 
     // light
@@ -68,6 +70,22 @@ fn setup(
 #[reflect(Component)]
 struct Rotates;
 
+/// Space anchors the hatching to the world, so it turns with the cube, or back to the screen
+fn toggle_hatching_space(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut hatchings: Query<&mut DoGHatching>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut hatching in &mut hatchings {
+        hatching.space = match hatching.space {
+            HatchingSpace::Screen => HatchingSpace::World,
+            HatchingSpace::World => HatchingSpace::Screen,
+        };
+    }
+}
+
 /// Rotates any entity around the x and y axis
 fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
     for mut transform in &mut query {
@@ -0,0 +1,97 @@
+//! Loads the settings of the camera from `assets/presets/*.dog.ron`. Press 1 or 2 to switch
+//! between the outline and the crosshatch preset.
+//!
+//! Run it with `cargo run --example preset_scene --features bevy/file_watcher` to see changes to
+//! the preset files while the example is running.
+
+use bevy::prelude::*;
+use bevy_dog::{plugin::DoGPlugin, preset::DoGPresetHandle};
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, DoGPlugin::default()))
+        .register_type::<Rotates>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, (switch_preset, rotate, quit))
+        .run();
+}
+
+fn quit(keyboard_input: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keyboard_input.pressed(KeyCode::Escape) {
+        exit.write(AppExit::Success);
+    }
+}
+
+fn switch_preset(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut cameras: Query<&mut DoGPresetHandle>,
+) {
+    let path = if keyboard_input.just_pressed(KeyCode::Digit1) {
+        "presets/outline.dog.ron"
+    } else if keyboard_input.just_pressed(KeyCode::Digit2) {
+        "presets/crosshatch.dog.ron"
+    } else {
+        return;
+    };
+    for mut preset in &mut cameras {
+        preset.0 = asset_server.load(path);
+    }
+}
+
+/// Set up a simple 3D scene
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    // circular base
+    commands.spawn((
+        Mesh3d(meshes.add(Circle::new(4.0))),
+        MeshMaterial3d(materials.add(Color::WHITE)),
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+    ));
+    // cube
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
+        MeshMaterial3d(materials.add(Color::srgb_u8(124, 144, 255))),
+        Transform::from_xyz(0.0, 0.5, 0.0),
+        Rotates,
+    ));
+    commands.spawn((
+        Mesh3d(meshes.add(Sphere::new(0.5))),
+        MeshMaterial3d(materials.add(Color::srgb_u8(255, 144, 124))),
+        Transform::from_xyz(0.0, 0.5, -1.5),
+        Rotates,
+    ));
+    // camera, the DoGSettings and PassesSettings are inserted once the preset has loaded
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_translation(Vec3::new(5.0, 3.0, 0.0)).looking_at(Vec3::default(), Vec3::Y),
+        DoGPresetHandle(asset_server.load("presets/crosshatch.dog.ron")),
+    ));
+
+    // light
+    commands.spawn((
+        SpotLight {
+            intensity: 500_000_000.,
+            shadows_enabled: true,
+            inner_angle: 0.0,
+            outer_angle: 0.8,
+            ..default()
+        },
+        Transform::from_xyz(5.0, 18.5, -5.0).looking_at(Vec3::ZERO, Vec3::Z),
+    ));
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct Rotates;
+
+/// Rotates any entity around the x and y axis
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(0.55 * time.delta_secs());
+    }
+}
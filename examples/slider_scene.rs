@@ -29,7 +29,7 @@ fn main() {
                     ..default()
                 }),
             DebugUIPlugin,
-            DoGPlugin,
+            DoGPlugin::default(),
         ))
         .register_type::<Rotates>()
         .add_systems(Startup, setup)
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_dog::{
+    plugin::DoGPlugin,
+    settings::{DoGSettings, PassesSettings},
+};
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, DoGPlugin::default()))
+        .register_type::<Rotates>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, (rotate, quit))
+        .run();
+}
+
+fn quit(keyboard_input: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keyboard_input.pressed(KeyCode::Escape) {
+        exit.write(AppExit::Success);
+    }
+}
+/// Set up a simple 2D scene
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // background
+    commands.spawn((
+        Mesh2d(meshes.add(Rectangle::new(900.0, 600.0))),
+        MeshMaterial2d(materials.add(Color::srgb_u8(230, 225, 210))),
+        Transform::from_xyz(0.0, 0.0, -1.0),
+    ));
+    // shapes
+    commands.spawn((
+        Mesh2d(meshes.add(Circle::new(120.0))),
+        MeshMaterial2d(materials.add(Color::srgb_u8(255, 144, 124))),
+        Transform::from_xyz(-200.0, 0.0, 0.0),
+    ));
+    commands.spawn((
+        Mesh2d(meshes.add(Rectangle::new(180.0, 180.0))),
+        MeshMaterial2d(materials.add(Color::srgb_u8(124, 144, 255))),
+        Transform::from_xyz(200.0, 0.0, 0.0),
+        Rotates,
+    ));
+    commands.spawn((
+        Mesh2d(meshes.add(RegularPolygon::new(90.0, 6))),
+        MeshMaterial2d(materials.add(Color::srgb_u8(120, 200, 140))),
+        Transform::from_xyz(0.0, 150.0, 0.0),
+        Rotates,
+    ));
+    // camera
+    commands.spawn((Camera2d, DoGSettings::OUTLINE, PassesSettings::default()));
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct Rotates;
+
+/// Rotates any entity around the z axis
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    for mut transform in &mut query {
+        transform.rotate_z(0.55 * time.delta_secs());
+    }
+}
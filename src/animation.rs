@@ -0,0 +1,149 @@
+//! Keyframes [`DoGSettings`] with bevy's animation system.
+//!
+//! Single fields can be animated with `animated_field!`, like the fields of any other component:
+//!
+//! ```
+//! # use bevy::{
+//! #     animation::{animated_field, animation_curves::AnimatedField, AnimationTargetId},
+//! #     prelude::*,
+//! # };
+//! # use bevy_dog::settings::DoGSettings;
+//! # let mut clip = AnimationClip::default();
+//! # let target = AnimationTargetId::from_name(&Name::new("camera"));
+//! clip.add_curve_to_target(
+//!     target,
+//!     AnimatableCurve::new(
+//!         animated_field!(DoGSettings::tau),
+//!         AnimatableKeyframeCurve::new([(0.0, 15.0), (2.0, 4.0)]).unwrap(),
+//!     ),
+//! );
+//! ```
+//!
+//! [`DoGSettingsProperty`] animates all settings at once, e.g. from one preset to another:
+//!
+//! ```
+//! # use bevy::{animation::AnimationTargetId, prelude::*};
+//! # use bevy_dog::{animation::DoGSettingsProperty, settings::DoGSettings};
+//! # let mut clip = AnimationClip::default();
+//! # let target = AnimationTargetId::from_name(&Name::new("camera"));
+//! clip.add_curve_to_target(
+//!     target,
+//!     AnimatableCurve::new(
+//!         DoGSettingsProperty,
+//!         EasingCurve::new(
+//!             DoGSettings::OUTLINE,
+//!             DoGSettings::CROSSHATCH,
+//!             EaseFunction::CubicInOut,
+//!         )
+//!         .reparametrize_linear(Interval::new(0.0, 2.0).unwrap())
+//!         .unwrap(),
+//!     ),
+//! );
+//! ```
+
+use crate::settings::DoGSettings;
+use bevy::{
+    animation::{
+        animatable::{Animatable, BlendInput},
+        animation_curves::{AnimatableProperty, EvaluatorId},
+        AnimationEntityMut, AnimationEvaluationError,
+    },
+    math::{
+        curve::{Ease, FunctionCurve, Interval},
+        FloatOrd,
+    },
+    prelude::*,
+};
+use std::any::TypeId;
+
+/// The continuous fields are interpolated, the discrete ones, like `thresholding` or
+/// `enable_hatch`, switch when the next keyframe is reached, like a `bool` does.
+impl Animatable for DoGSettings {
+    fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        if t < 1.0 {
+            a.lerp_continuous(b, t)
+        } else {
+            b.lerp_continuous(a, 1.0 - t)
+        }
+    }
+
+    /// Blends every continuous field like an `f32` or a vector is blended, the discrete fields
+    /// are taken from the input with the highest weight.
+    fn blend(inputs: impl Iterator<Item = BlendInput<Self>>) -> Self {
+        let inputs: Vec<_> = inputs.collect();
+        let Some(heaviest) = inputs.iter().max_by_key(|input| FloatOrd(input.weight)) else {
+            return DoGSettings::default();
+        };
+
+        fn field<T: Animatable>(
+            inputs: &[BlendInput<DoGSettings>],
+            field: impl Fn(&DoGSettings) -> T,
+        ) -> T {
+            T::blend(inputs.iter().map(|input| BlendInput {
+                weight: input.weight,
+                value: field(&input.value),
+                additive: input.additive,
+            }))
+        }
+
+        DoGSettings {
+            sigma_c: field(&inputs, |settings| settings.sigma_c),
+            sigma_e: field(&inputs, |settings| settings.sigma_e),
+            sigma_m: field(&inputs, |settings| settings.sigma_m),
+            sigma_a: field(&inputs, |settings| settings.sigma_a),
+            quantizer_step: field(&inputs, |settings| settings.quantizer_step),
+            k: field(&inputs, |settings| settings.k),
+            tau: field(&inputs, |settings| settings.tau),
+            phi: field(&inputs, |settings| settings.phi),
+            blend_strength: field(&inputs, |settings| settings.blend_strength),
+            dog_strength: field(&inputs, |settings| settings.dog_strength),
+            line_conv_step_sizes: field(&inputs, |settings| settings.line_conv_step_sizes),
+            edge_smooth_step_sizes: field(&inputs, |settings| settings.edge_smooth_step_sizes),
+            min_color: field(&inputs, |settings| settings.min_color),
+            max_color: field(&inputs, |settings| settings.max_color),
+            hatch_resolutions: field(&inputs, |settings| settings.hatch_resolutions),
+            hatch_rotations: field(&inputs, |settings| settings.hatch_rotations),
+            world_hatch_scale: field(&inputs, |settings| settings.world_hatch_scale),
+            hatch_spacings: field(&inputs, |settings| settings.hatch_spacings),
+            hatch_thicknesses: field(&inputs, |settings| settings.hatch_thicknesses),
+            hatch_jitters: field(&inputs, |settings| settings.hatch_jitters),
+            hatch_wobbles: field(&inputs, |settings| settings.hatch_wobbles),
+            thresholds: field(&inputs, |settings| settings.thresholds),
+            ..heaviest.value
+        }
+    }
+}
+
+/// Lets [`EasingCurve`] and the other curve constructors build curves of whole settings.
+impl Ease for DoGSettings {
+    fn interpolating_curve_unbounded(start: Self, end: Self) -> impl Curve<Self> {
+        FunctionCurve::new(Interval::EVERYWHERE, move |t| {
+            DoGSettings::interpolate(&start, &end, t)
+        })
+    }
+}
+
+/// The whole [`DoGSettings`] component as an [`AnimatableProperty`], to animate it with an
+/// [`AnimatableCurve`] of settings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DoGSettingsProperty;
+
+impl AnimatableProperty for DoGSettingsProperty {
+    type Property = DoGSettings;
+
+    fn get_mut<'a>(
+        &self,
+        entity: &'a mut AnimationEntityMut,
+    ) -> Result<&'a mut DoGSettings, AnimationEvaluationError> {
+        entity
+            .get_mut::<DoGSettings>()
+            .map(Mut::into_inner)
+            .ok_or_else(|| {
+                AnimationEvaluationError::ComponentNotPresent(TypeId::of::<DoGSettings>())
+            })
+    }
+
+    fn evaluator_id(&self) -> EvaluatorId<'_> {
+        EvaluatorId::Type(TypeId::of::<Self>())
+    }
+}
@@ -0,0 +1,268 @@
+//! Stylizes images with the cpu passes, e.g. to preview settings on concept art without running a
+//! game. Build it with `cargo install bevy_dog --features cli` or
+//! `cargo run --features cli -- --help`.
+
+use bevy::reflect::{
+    serde::{TypedReflectDeserializer, TypedReflectSerializer},
+    FromReflect, GetTypeRegistration, TypeRegistry,
+};
+use bevy_dog::{
+    cpu,
+    preset::DoGPreset,
+    settings::{DoGSettings, PassesSettings},
+};
+use image::{Rgba32FImage, RgbaImage};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+Stylizes images with the difference of gaussians.
+
+Usage: bevy_dog [OPTIONS] <INPUT>...
+
+Arguments:
+  <INPUT>...  png or jpeg files, or directories of frames, which are processed in file name order
+
+Options:
+  -o, --output <DIR>       Where the stylized images are written to [default: stylized]
+  -p, --preset <PRESET>    default, outline, outline_dither or crosshatch [default: outline]
+  -s, --settings <FILE>    A ron file with DoGSettings or a .dog.ron preset with settings and
+                           passes, replaces the preset
+      --print-settings     Prints the settings as ron, to start a settings file from, and exits
+      --fdog               Uses the flow-based DoG
+      --anti-aliasing      Smooths the lines along the flow field
+      --scale <SCALE>      Runs the passes at this fraction of the resolution, e.g. 0.5
+      --intermediates      Also writes the lab color, flow field, gaussians and lines
+  -h, --help               Prints this help
+";
+
+struct Args {
+    inputs: Vec<PathBuf>,
+    output: PathBuf,
+    settings: DoGSettings,
+    passes: PassesSettings,
+    print_settings: bool,
+    intermediates: bool,
+}
+
+fn preset(name: &str) -> Result<DoGSettings, String> {
+    DoGSettings::PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, settings)| *settings)
+        .ok_or_else(|| format!("unknown preset `{name}`"))
+}
+
+fn settings_registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::default();
+    registry.register::<DoGPreset>();
+    registry
+}
+
+fn is_preset(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.to_ascii_lowercase().ends_with(".dog.ron"))
+}
+
+/// Reads a `.dog.ron` preset, or a file with only [`DoGSettings`] which keeps the passes.
+fn read_settings(path: &Path, passes: PassesSettings) -> Result<DoGPreset, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("can't read {}: {error}", path.display()))?;
+    let registry = settings_registry();
+    if is_preset(path) {
+        return DoGPreset::from_ron(&source, &registry)
+            .map_err(|error| format!("{}: {error}", path.display()));
+    }
+    let registration = DoGSettings::get_type_registration();
+    let settings = ron::Options::default()
+        .from_str_seed(
+            &source,
+            TypedReflectDeserializer::new(&registration, &registry),
+        )
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    let settings = DoGSettings::from_reflect(settings.as_partial_reflect())
+        .ok_or_else(|| format!("{} doesn't contain DoGSettings", path.display()))?;
+    Ok(DoGPreset { settings, passes })
+}
+
+fn print_settings(settings: &DoGSettings) -> Result<(), String> {
+    let registry = settings_registry();
+    let serializer = TypedReflectSerializer::new(settings, &registry);
+    let ron = ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+    println!("{ron}");
+    Ok(())
+}
+
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut args = Args {
+        inputs: Vec::new(),
+        output: PathBuf::from("stylized"),
+        settings: DoGSettings::OUTLINE,
+        passes: PassesSettings::default(),
+        print_settings: false,
+        intermediates: false,
+    };
+    let mut settings_file = None;
+    // the pass options go on top of the passes of a preset file
+    let mut fdog = false;
+    let mut anti_aliasing = false;
+    let mut resolution_scale = None;
+
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("`{argument}` needs a value"))
+        };
+        match argument.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => args.output = value()?.into(),
+            "-p" | "--preset" => args.settings = preset(&value()?)?,
+            "-s" | "--settings" => settings_file = Some(PathBuf::from(value()?)),
+            "--print-settings" => args.print_settings = true,
+            "--fdog" => fdog = true,
+            "--anti-aliasing" => anti_aliasing = true,
+            "--scale" => {
+                let scale = value()?;
+                resolution_scale = match scale.parse::<f32>() {
+                    Ok(scale) if scale > 0.0 && scale <= 1.0 => Some(scale),
+                    _ => return Err(format!("the scale has to be in (0, 1], not `{scale}`")),
+                };
+            }
+            "--intermediates" => args.intermediates = true,
+            _ if argument.starts_with('-') => {
+                return Err(format!("unknown option `{argument}`"));
+            }
+            _ => args.inputs.push(argument.into()),
+        }
+    }
+
+    if let Some(path) = settings_file {
+        let preset = read_settings(&path, args.passes)?;
+        args.settings = preset.settings;
+        args.passes = preset.passes;
+    }
+    args.passes.fdog |= fdog;
+    args.passes.anti_aliasing |= anti_aliasing;
+    if let Some(scale) = resolution_scale {
+        args.passes.resolution_scale = scale;
+    }
+    if args.inputs.is_empty() && !args.print_settings {
+        return Err("no input images given".into());
+    }
+    Ok(Some(args))
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            matches!(
+                extension.to_ascii_lowercase().as_str(),
+                "png" | "jpg" | "jpeg"
+            )
+        })
+}
+
+/// The images to process, directories are expanded to the images in them.
+fn collect_images(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut images = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let entries = std::fs::read_dir(input)
+                .map_err(|error| format!("can't read {}: {error}", input.display()))?;
+            let mut frames: Vec<_> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && is_image(path))
+                .collect();
+            frames.sort();
+            images.extend(frames);
+        } else {
+            images.push(input.clone());
+        }
+    }
+    Ok(images)
+}
+
+/// Maps a float buffer to a png, `offset` and `scale` bring the channels to [0, 1].
+fn save_buffer(buffer: &Rgba32FImage, path: &Path, offset: f32, scale: f32) -> Result<(), String> {
+    let image = RgbaImage::from_fn(buffer.width(), buffer.height(), |x, y| {
+        let [r, g, b, _] = buffer.get_pixel(x, y).0;
+        let encode = |c: f32| ((c * scale + offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+        image::Rgba([encode(r), encode(g), encode(b), 255])
+    });
+    image
+        .save(path)
+        .map_err(|error| format!("can't write {}: {error}", path.display()))
+}
+
+fn stylize(args: &Args, input: &Path) -> Result<(), String> {
+    let image = image::open(input)
+        .map_err(|error| format!("can't open {}: {error}", input.display()))?
+        .to_rgba8();
+    let name = input
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("output");
+    let output_path = |suffix: &str| args.output.join(format!("{name}{suffix}.png"));
+
+    let (output, intermediates) =
+        cpu::process_with_intermediates(&image, &args.settings, &args.passes);
+    output
+        .save(output_path(""))
+        .map_err(|error| format!("can't write {}: {error}", output_path("").display()))?;
+
+    if args.intermediates {
+        save_buffer(&intermediates.lab, &output_path("_lab"), 0.0, 1.0)?;
+        if let Some(flow_field) = &intermediates.flow_field {
+            // the flow direction is in [-1, 1]
+            save_buffer(flow_field, &output_path("_flow_field"), 0.5, 0.5)?;
+        }
+        save_buffer(
+            &intermediates.gaussians,
+            &output_path("_gaussians"),
+            0.0,
+            1.0,
+        )?;
+        save_buffer(&intermediates.mask, &output_path("_mask"), 0.0, 1.0)?;
+        if let Some(anti_aliased) = &intermediates.anti_aliased {
+            save_buffer(anti_aliased, &output_path("_anti_aliased"), 0.0, 1.0)?;
+        }
+    }
+
+    println!("{} -> {}", input.display(), output_path("").display());
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let Some(args) = parse_args()? else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+    if args.print_settings {
+        return print_settings(&args.settings);
+    }
+
+    let images = collect_images(&args.inputs)?;
+    std::fs::create_dir_all(&args.output)
+        .map_err(|error| format!("can't create {}: {error}", args.output.display()))?;
+    for image in &images {
+        stylize(&args, image)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,884 @@
+//! A CPU implementation of the passes, which runs without a GPU.
+//!
+//! Every pass mirrors its fragment shader in `assets/shaders`, down to how the textures are
+//! sampled, so it can process images in tools and serves as a reference for the shaders.
+//! The compute backend clamps the kernel radii, the reference always uses the full kernels.
+//! The hatching draws every layer with its bundled texture, like on a camera without a
+//! [`DoGHatching`](crate::hatching::DoGHatching), or with the procedural strokes. A
+//! [`HatchingMode::TonalArtMap`] uses the four bundled textures as its tones with their stroke
+//! mips, like [`DoGHatching::tonal_art_map`](crate::hatching::DoGHatching::tonal_art_map) with
+//! them, and picks the mip level from how many texels a pixel covers, like `textureSampleGrad`.
+
+use crate::{
+    hatching::{add_stroke_mips, HatchingMode},
+    plugin::{crosshatch_image, CROSSHATCH_TEXTURE_BYTES},
+    settings::{DoGPass, DoGSettings, PassesSettings, Thresholding},
+};
+use bevy::{
+    color::{ColorToComponents, ColorToPacked, LinearRgba, Srgba},
+    math::{IVec2, UVec2, Vec2, Vec3, Vec3Swizzles, Vec4, Vec4Swizzles},
+};
+use image::{Rgba32FImage, RgbaImage};
+use std::f32::consts::PI;
+
+/// Runs the effect on an sRGB image, like on the view target of a camera without hdr.
+pub fn process(image: &RgbaImage, settings: &DoGSettings, passes: &PassesSettings) -> RgbaImage {
+    process_with_intermediates(image, settings, passes).0
+}
+
+/// Like [`process`], but also returns the textures of the passes before the blend.
+pub fn process_with_intermediates(
+    image: &RgbaImage,
+    settings: &DoGSettings,
+    passes: &PassesSettings,
+) -> (RgbaImage, Intermediates) {
+    process_srgb(image, settings, passes, HatchingMode::Layers)
+}
+
+/// Like [`process`], with the hatching picked by `mode`.
+pub fn process_with_hatching(
+    image: &RgbaImage,
+    settings: &DoGSettings,
+    passes: &PassesSettings,
+    mode: HatchingMode,
+) -> RgbaImage {
+    process_srgb(image, settings, passes, mode).0
+}
+
+fn process_srgb(
+    image: &RgbaImage,
+    settings: &DoGSettings,
+    passes: &PassesSettings,
+    mode: HatchingMode,
+) -> (RgbaImage, Intermediates) {
+    let source = Texture::from_fn(UVec2::from(image.dimensions()), |coords| {
+        let [r, g, b, a] = image.get_pixel(coords.x, coords.y).0;
+        LinearRgba::from(Srgba::rgba_u8(r, g, b, a)).to_vec4()
+    });
+
+    let textures = run(&source, settings, passes, mode);
+
+    let output = &textures.output;
+    let output = RgbaImage::from_fn(output.size.x, output.size.y, |x, y| {
+        // the unorm view target of a camera without hdr clamps the colors
+        let color = output.texel(UVec2::new(x, y)).clamp(Vec4::ZERO, Vec4::ONE);
+        image::Rgba(Srgba::from(LinearRgba::from_vec4(color)).to_u8_array())
+    });
+    (output, textures.intermediates())
+}
+
+/// Runs the effect on an image with linear colors, like on the view target of a hdr camera, so
+/// colors above 1 pass through for the tonemapping.
+pub fn process_linear(
+    image: &Rgba32FImage,
+    settings: &DoGSettings,
+    passes: &PassesSettings,
+) -> Rgba32FImage {
+    let source = Texture::from_fn(UVec2::from(image.dimensions()), |coords| {
+        Vec4::from_array(image.get_pixel(coords.x, coords.y).0)
+    });
+
+    run(&source, settings, passes, HatchingMode::Layers)
+        .output
+        .to_image()
+}
+
+/// The textures the passes write before the blend, at the reduced resolution of
+/// [`PassesSettings::resolution_scale`].
+pub struct Intermediates {
+    /// The lightness in r, the a and b components of the lab color mapped to [0, 1] in g and b.
+    pub lab: Rgba32FImage,
+    /// The flow direction in r and g and the edge strength in b, if a pass needs the flow field.
+    pub flow_field: Option<Rgba32FImage>,
+    /// The two gaussians of the DoG in r and g, the FDoG also stores their difference in b.
+    pub gaussians: Rgba32FImage,
+    /// The thresholded lines, one layer per channel.
+    pub mask: Rgba32FImage,
+    /// The lines after the anti aliasing pass, if it runs.
+    pub anti_aliased: Option<Rgba32FImage>,
+}
+
+/// Every texture the node writes.
+struct Textures {
+    lab: Texture,
+    flow_field: Option<Texture>,
+    gaussians: Texture,
+    mask: Texture,
+    anti_aliased: Option<Texture>,
+    output: Texture,
+}
+
+impl Textures {
+    fn intermediates(&self) -> Intermediates {
+        Intermediates {
+            lab: self.lab.to_image(),
+            flow_field: self.flow_field.as_ref().map(Texture::to_image),
+            gaussians: self.gaussians.to_image(),
+            mask: self.mask.to_image(),
+            anti_aliased: self.anti_aliased.as_ref().map(Texture::to_image),
+        }
+    }
+}
+
+/// The passes in the order the node runs them. `source` holds linear colors, like the view target
+/// does when it is sampled.
+fn run(
+    source: &Texture,
+    settings: &DoGSettings,
+    passes: &PassesSettings,
+    mode: HatchingMode,
+) -> Textures {
+    let enabled = passes.passes();
+    let size = (source.size.as_vec2() * passes.resolution_scale.clamp(0.0, 1.0))
+        .round()
+        .as_uvec2()
+        .max(UVec2::ONE);
+
+    // how many pixels of the source a texel covers, the shader takes it from the uv derivatives
+    let footprint = source.size.as_vec2() / size.as_vec2();
+    let lab = Texture::fragment_pass(size, |uv| lab_pass(source, uv, footprint));
+
+    let flow_field = enabled.contains(DoGPass::FlowField).then(|| {
+        let eigen = Texture::fragment_pass(size, |uv| eigenvector_pass(&lab, uv));
+        let horizontal =
+            Texture::fragment_pass(size, |uv| tensor_blur_pass(&eigen, settings, uv, Vec2::X));
+        Texture::fragment_pass(size, |uv| {
+            flow_from_tensor(tensor_blur_pass(&horizontal, settings, uv, Vec2::Y).xyz())
+        })
+    });
+
+    let (gaussians, mask) = match &flow_field {
+        Some(flow_field) if enabled.contains(DoGPass::FDoG) => {
+            let first =
+                Texture::fragment_pass(size, |uv| fdog_blur_pass(&lab, flow_field, settings, uv));
+            let mask = Texture::fragment_pass(size, |uv| {
+                unorm8(fdog_blur_and_difference(&first, flow_field, settings, uv))
+            });
+            (first, mask)
+        }
+        _ => {
+            let first =
+                Texture::fragment_pass(size, |uv| first_gaussian_blur_pass(&lab, settings, uv));
+            let mask = Texture::fragment_pass(size, |uv| {
+                unorm8(second_gaussian_blur_pass(&first, settings, uv))
+            });
+            (first, mask)
+        }
+    };
+
+    let anti_aliased = flow_field
+        .as_ref()
+        .filter(|_| enabled.contains(DoGPass::AntiAliasing))
+        .map(|flow_field| {
+            Texture::fragment_pass(size, |uv| {
+                unorm8(anti_aliasing_pass(&mask, flow_field, settings, uv))
+            })
+        });
+
+    let lines = anti_aliased.as_ref().unwrap_or(&mask);
+    let hatches = [0, 1, 2, 3].map(|index| crosshatch_texture(index, mode));
+    let output = match mode {
+        // every layer uses its crosshatch texture, like cameras without a `DoGHatching`
+        HatchingMode::Layers => Texture::fragment_pass(source.size, |uv| {
+            blend_pass(source, lines, hatches.each_ref(), settings, uv)
+        }),
+        HatchingMode::TonalArtMap => Texture::fragment_pass(source.size, |uv| {
+            tonal_art_map_blend_pass(source, lines, hatches.each_ref(), settings, uv)
+        }),
+    };
+
+    Textures {
+        lab,
+        flow_field,
+        gaussians,
+        mask,
+        anti_aliased,
+        output,
+    }
+}
+
+/// A hatch texture with its mip levels, the largest first.
+struct MipChain {
+    levels: Vec<Texture>,
+}
+
+impl MipChain {
+    fn size(&self) -> UVec2 {
+        self.levels[0].size
+    }
+
+    /// `textureSampleGrad` with the trilinear sampler of the hatching, that repeats the texture.
+    /// `footprint` is how many texels of the first level a pixel covers.
+    fn sample_grad(&self, uv: Vec2, footprint: f32) -> Vec4 {
+        let lod = footprint.log2().clamp(0.0, (self.levels.len() - 1) as f32);
+        let level = lod.floor() as usize;
+        let sample = self.levels[level].sample_repeat(uv);
+        if lod.fract() == 0.0 {
+            return sample;
+        }
+        sample.lerp(self.levels[level + 1].sample_repeat(uv), lod.fract())
+    }
+}
+
+/// A texture with linear float colors and the sampling the shaders use.
+struct Texture {
+    size: UVec2,
+    texels: Vec<Vec4>,
+}
+
+impl Texture {
+    fn from_fn(size: UVec2, mut texel: impl FnMut(UVec2) -> Vec4) -> Self {
+        let texels = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(&mut texel)
+            .collect();
+        Self { size, texels }
+    }
+
+    /// Runs `fragment` for every texel with the uv the fullscreen triangle gives it.
+    fn fragment_pass(size: UVec2, fragment: impl Fn(Vec2) -> Vec4) -> Self {
+        Self::from_fn(size, |coords| {
+            fragment((coords.as_vec2() + 0.5) / size.as_vec2())
+        })
+    }
+
+    fn texel(&self, coords: UVec2) -> Vec4 {
+        self.texels[(coords.y * self.size.x + coords.x) as usize]
+    }
+
+    /// `textureLoad` with the coordinates clamped to the texture.
+    fn load(&self, coords: IVec2) -> Vec4 {
+        self.texel(
+            coords
+                .clamp(IVec2::ZERO, self.size.as_ivec2() - 1)
+                .as_uvec2(),
+        )
+    }
+
+    /// `textureSample` with a linear sampler, that clamps to the edge.
+    fn sample(&self, uv: Vec2) -> Vec4 {
+        self.bilinear(uv, |coords| self.load(coords))
+    }
+
+    /// `textureSample` with a linear sampler, that repeats the texture.
+    fn sample_repeat(&self, uv: Vec2) -> Vec4 {
+        let size = self.size.as_ivec2();
+        self.bilinear(uv, |coords| self.texel(coords.rem_euclid(size).as_uvec2()))
+    }
+
+    /// `textureSample` with the point clamp sampler.
+    fn sample_nearest(&self, uv: Vec2) -> Vec4 {
+        self.load((uv * self.size.as_vec2()).floor().as_ivec2())
+    }
+
+    fn bilinear(&self, uv: Vec2, load: impl Fn(IVec2) -> Vec4) -> Vec4 {
+        let position = uv * self.size.as_vec2() - 0.5;
+        let base = position.floor().as_ivec2();
+        let f = position - position.floor();
+
+        let top = load(base).lerp(load(base + IVec2::X), f.x);
+        let bottom = load(base + IVec2::Y).lerp(load(base + IVec2::ONE), f.x);
+        top.lerp(bottom, f.y)
+    }
+
+    fn to_image(&self) -> Rgba32FImage {
+        Rgba32FImage::from_fn(self.size.x, self.size.y, |x, y| {
+            image::Rgba(self.texel(UVec2::new(x, y)).to_array())
+        })
+    }
+
+    /// The width and height of a texel in uv coordinates.
+    fn texel_size(&self) -> Vec2 {
+        1.0 / self.size.as_vec2()
+    }
+}
+
+/// A bundled crosshatch texture of the blend pass, which is stored as sRGB like on the GPU.
+/// A bundled crosshatch texture as the blend pass draws it, with the stroke mips of the copy a
+/// tonal art map gets.
+fn crosshatch_texture(index: usize, mode: HatchingMode) -> MipChain {
+    let mut image = crosshatch_image(CROSSHATCH_TEXTURE_BYTES[index]);
+    if mode == HatchingMode::TonalArtMap {
+        add_stroke_mips(&mut image);
+    }
+    let data = image.data.as_ref().unwrap();
+    let mut offset = 0;
+    let levels = (0..image.texture_descriptor.mip_level_count)
+        .map(|level| {
+            let size = (image.size() >> level).max(UVec2::ONE);
+            let level = Texture::from_fn(size, |coords| {
+                let texel = &data[offset + (coords.y * size.x + coords.x) as usize * 4..];
+                LinearRgba::from(Srgba::rgba_u8(texel[0], texel[1], texel[2], texel[3])).to_vec4()
+            });
+            offset += (size.x * size.y) as usize * 4;
+            level
+        })
+        .collect();
+    MipChain { levels }
+}
+
+/// The precision of the `Rgba8Unorm` textures the mask and the anti aliased lines are stored in.
+fn unorm8(color: Vec4) -> Vec4 {
+    (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.0).round() / 255.0
+}
+
+fn gaussian(sigma: f32, pos: f32) -> f32 {
+    (1. / (2. * PI * sigma * sigma).sqrt()) * (-(pos * pos) / (2. * sigma * sigma)).exp()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// rgb2lab.wgsl
+
+fn rgb2xyz(c: Vec3) -> Vec3 {
+    let decode = |c: f32| {
+        if c > 0.04045 {
+            ((c + 0.055) / 1.055).powf(2.4)
+        } else {
+            c / 12.92
+        }
+    };
+    let tmp = Vec3::new(decode(c.x), decode(c.y), decode(c.z));
+
+    100.0
+        * Vec3::new(
+            tmp.dot(Vec3::new(0.4124, 0.3576, 0.1805)),
+            tmp.dot(Vec3::new(0.2126, 0.7152, 0.0722)),
+            tmp.dot(Vec3::new(0.0193, 0.1192, 0.9505)),
+        )
+}
+
+fn xyz2lab(c: Vec3) -> Vec3 {
+    let n = c / Vec3::new(95.047, 100.0, 108.883);
+    let f = |n: f32| {
+        if n > 0.008856 {
+            n.powf(1.0 / 3.0)
+        } else {
+            (7.787 * n) + (16.0 / 116.0)
+        }
+    };
+    let v = Vec3::new(f(n.x), f(n.y), f(n.z));
+    Vec3::new(
+        (116.0 * v.y) - 16.0,
+        500.0 * (v.x - v.y),
+        200.0 * (v.y - v.z),
+    )
+}
+
+const MAX_SOURCE_TAPS: f32 = 8.0;
+
+/// Averages the pixels of the source a texel covers with bilinear taps two pixels apart.
+fn sample_source(source: &Texture, uv: Vec2, footprint: Vec2) -> Vec3 {
+    let taps = (footprint / 2.0)
+        .ceil()
+        .clamp(Vec2::ONE, Vec2::splat(MAX_SOURCE_TAPS))
+        .as_uvec2();
+    let mut sum = Vec3::ZERO;
+    for y in 0..taps.y {
+        for x in 0..taps.x {
+            let offset = ((UVec2::new(x, y).as_vec2() + 0.5) / taps.as_vec2() - 0.5) * footprint;
+            sum += source.sample(uv + offset / source.size.as_vec2()).xyz();
+        }
+    }
+    sum / (taps.x * taps.y) as f32
+}
+
+fn lab_pass(source: &Texture, uv: Vec2, footprint: Vec2) -> Vec4 {
+    let lab = xyz2lab(rgb2xyz(sample_source(source, uv, footprint)));
+    Vec4::new(
+        lab.x / 100.0,
+        0.5 + 0.5 * (lab.y / 127.0),
+        0.5 + 0.5 * (lab.z / 127.0),
+        1.0,
+    )
+}
+
+// tfm.wgsl
+
+fn eigenvector_pass(lab: &Texture, uv: Vec2) -> Vec4 {
+    let texel_size = lab.texel_size();
+    let s = |x: f32, y: f32| lab.sample(uv + Vec2::new(x, y) * texel_size).xyz();
+
+    let sx =
+        (s(-1., -1.) + 2.0 * s(-1., 0.) + s(-1., 1.) - s(1., -1.) - 2.0 * s(1., 0.) - s(1., 1.))
+            / 4.0;
+    let sy =
+        (s(-1., -1.) + 2.0 * s(0., -1.) + s(1., -1.) - s(-1., 1.) - 2.0 * s(0., 1.) - s(1., 1.))
+            / 4.0;
+
+    Vec4::new(sx.dot(sx), sy.dot(sy), sx.dot(sy), 1.0)
+}
+
+/// One of the separable gaussian blurs of the structure tensor along `direction`.
+fn tensor_blur_pass(tensor: &Texture, settings: &DoGSettings, uv: Vec2, direction: Vec2) -> Vec4 {
+    let texel_size = tensor.texel_size();
+    let kernel_radius = (settings.sigma_c * 2.45).floor().max(1.0);
+    let mut col = Vec4::ZERO;
+    let mut kernel_sum = 0.0;
+
+    let mut x = -kernel_radius;
+    while x <= kernel_radius {
+        let gauss = gaussian(settings.sigma_c, x);
+        col += tensor.sample(uv + direction * x * texel_size) * gauss;
+        kernel_sum += gauss;
+        x += 1.0;
+    }
+
+    col / kernel_sum
+}
+
+/// Turns the blurred structure tensor into the flow direction and the edge strength.
+fn flow_from_tensor(g: Vec3) -> Vec4 {
+    let lambda1 =
+        0.5 * (g.y + g.x + (g.y * g.y - 2.0 * g.x * g.y + g.x * g.x + 4.0 * g.z * g.z).sqrt());
+    let d = Vec2::new(g.x - lambda1, g.z);
+    if d.length() != 0.0 {
+        d.normalize().extend(lambda1.sqrt()).extend(1.0)
+    } else {
+        Vec4::new(0., 1., 0., 1.)
+    }
+}
+
+// dog.wgsl and fdog.wgsl
+
+/// Applies the thresholding to the difference of the two gaussians.
+fn threshold(d: f32, settings: &DoGSettings) -> Vec4 {
+    let mut output = match settings.thresholding {
+        Thresholding::Tanh => {
+            let layer = |threshold: f32| {
+                if d >= threshold {
+                    1.0
+                } else {
+                    1.0 + (settings.phi * (d - threshold)).tanh()
+                }
+            };
+            Vec4::from_array(settings.thresholds.to_array().map(layer))
+        }
+        Thresholding::Quantization => {
+            let a = 1.0 / settings.quantizer_step;
+            let b = settings.thresholds.x / 100.0;
+            let x = d / 100.0;
+
+            Vec4::splat(if x >= b {
+                1.0
+            } else {
+                a * ((x.powf(settings.phi) - (a * b / 2.)) / (a * b) + 0.5).floor()
+            })
+        }
+        Thresholding::SmoothQuantization => {
+            let x = d / 100.;
+            let qn = (x * settings.quantizer_step + 0.5).floor() / settings.quantizer_step;
+            let qs = smoothstep(-2.0, 2.0, settings.phi * (x - qn) * 10.0) - 0.5;
+            Vec4::splat(qn + qs / settings.quantizer_step)
+        }
+        Thresholding::NoThreshold => Vec4::splat(d / 100.0),
+    };
+
+    if settings.invert {
+        output = 1.0 - output;
+    }
+
+    output.clamp(Vec4::ZERO, Vec4::ONE)
+}
+
+fn dog_kernel_size(settings: &DoGSettings) -> f32 {
+    if settings.sigma_e * 2. > 2. {
+        (settings.sigma_e * 2.).floor()
+    } else {
+        2.0
+    }
+}
+
+fn first_gaussian_blur_pass(lab: &Texture, settings: &DoGSettings, uv: Vec2) -> Vec4 {
+    let texel_size = lab.texel_size();
+    let mut col = Vec2::ZERO;
+    let mut kernel_sum = Vec2::ZERO;
+    let kernel_size = dog_kernel_size(settings);
+
+    let mut x = -kernel_size;
+    while x <= kernel_size {
+        let c = lab.sample(uv + Vec2::new(x, 0.) * texel_size).x;
+        let gauss = Vec2::new(
+            gaussian(settings.sigma_e, x),
+            gaussian(settings.sigma_e * settings.k, x),
+        );
+        col += c * gauss;
+        kernel_sum += gauss;
+        x += 1.0;
+    }
+
+    (col / kernel_sum).extend(0.).extend(0.)
+}
+
+fn second_gaussian_blur_pass(first: &Texture, settings: &DoGSettings, uv: Vec2) -> Vec4 {
+    let texel_size = first.texel_size();
+    let mut col = Vec2::ZERO;
+    let mut kernel_sum = Vec2::ZERO;
+    let kernel_size = dog_kernel_size(settings);
+
+    let mut y = -kernel_size;
+    while y <= kernel_size {
+        let c = first.sample(uv + Vec2::new(0., y) * texel_size).xy();
+        let gauss = Vec2::new(
+            gaussian(settings.sigma_e, y),
+            gaussian(settings.sigma_e * settings.k, y),
+        );
+        col += c * gauss;
+        kernel_sum += gauss;
+        y += 1.0;
+    }
+
+    let g = col / kernel_sum;
+    let d = (1. + settings.tau) * (g.x * 100.0) - settings.tau * (g.y * 100.0);
+
+    threshold(d, settings)
+}
+
+fn fdog_blur_pass(lab: &Texture, flow_field: &Texture, settings: &DoGSettings, uv: Vec2) -> Vec4 {
+    // direction of the vector flow
+    let tensor = flow_field.sample_nearest(uv).xy();
+    let n = Vec2::new(tensor.y, -tensor.x);
+    let ds = 1.0 / n.abs().max_element();
+    let n = n * lab.texel_size();
+
+    let mut col = Vec2::splat(lab.sample(uv).x);
+    let mut kernel_sum = Vec2::ONE;
+    let kernel_size = if settings.sigma_e * 2.0 > 1.0 {
+        (settings.sigma_e * 2.0).floor()
+    } else {
+        1.0
+    };
+
+    let mut x = ds;
+    while x <= kernel_size {
+        let gauss = Vec2::new(
+            gaussian(settings.sigma_e, x),
+            gaussian(settings.sigma_e * settings.k, x),
+        );
+        let c1 = lab.sample(uv - x * n).x;
+        let c2 = lab.sample(uv + x * n).x;
+
+        col += (c1 + c2) * gauss;
+        kernel_sum += 2.0 * gauss;
+        x += 1.0;
+    }
+    col /= kernel_sum;
+
+    Vec4::new(
+        col.x,
+        col.y,
+        (1. + settings.tau) * (col.x * 100.0) - settings.tau * (col.y * 100.0),
+        1.0,
+    )
+}
+
+fn fdog_blur_and_difference(
+    first: &Texture,
+    flow_field: &Texture,
+    settings: &DoGSettings,
+    uv: Vec2,
+) -> Vec4 {
+    let texel_size = first.texel_size();
+    let kernel_size = settings.sigma_m * 2.0;
+
+    let mut w = Vec2::ONE;
+    let c = first.sample(uv).xyz();
+    let mut g = if settings.calc_diff_before_convolution {
+        Vec2::new(c.z, 0.0)
+    } else {
+        c.xy()
+    };
+    let tfm_frag = flow_field.sample_nearest(uv).xy() * texel_size;
+
+    // follows the streamline forwards with the first step size and backwards with the second
+    for (sign, step_size) in [
+        (1.0, settings.line_conv_step_sizes.x),
+        (-1.0, settings.line_conv_step_sizes.y),
+    ] {
+        let mut st = uv;
+        let mut v = tfm_frag;
+        let mut d = 1.0;
+        while d < kernel_size {
+            st += sign * v * step_size;
+            let c = first.sample(st).xyz();
+            let gauss1 = gaussian(settings.sigma_m, d);
+
+            if settings.calc_diff_before_convolution {
+                g.x += gauss1 * c.z;
+                w.x += gauss1;
+            } else {
+                let gauss2 = gaussian(settings.sigma_m * settings.k, d);
+                g += Vec2::new(gauss1, gauss2) * c.xy();
+                w += Vec2::new(gauss1, gauss2);
+            }
+
+            v = flow_field.sample_nearest(st).xy() * texel_size;
+            d += 1.0;
+        }
+    }
+
+    g /= w;
+
+    let d = if settings.calc_diff_before_convolution {
+        g.x
+    } else {
+        (1. + settings.tau) * (g.x * 100.0) - settings.tau * (g.y * 100.0)
+    };
+
+    threshold(d, settings)
+}
+
+// aa.wgsl
+
+fn anti_aliasing_pass(
+    mask: &Texture,
+    flow_field: &Texture,
+    settings: &DoGSettings,
+    uv: Vec2,
+) -> Vec4 {
+    let texel_size = mask.texel_size();
+    let kernel_size = settings.sigma_a * 2.0;
+    let mut g = mask.sample(uv);
+    let mut w = 1.0;
+    let v = flow_field.sample_nearest(uv).xy() * texel_size;
+
+    for (sign, step_size) in [
+        (1.0, settings.edge_smooth_step_sizes.x),
+        (-1.0, settings.edge_smooth_step_sizes.y),
+    ] {
+        let mut st = uv;
+        let mut v = v;
+        let mut d = 1.0;
+        while d < kernel_size {
+            st += sign * v * step_size;
+            let gauss1 = gaussian(settings.sigma_a, d);
+            g += gauss1 * mask.sample(st);
+            w += gauss1;
+
+            v = flow_field.sample_nearest(st).xy() * texel_size;
+            d += 1.0;
+        }
+    }
+
+    g / w
+}
+
+// blend.wgsl
+
+/// How fast the weight of a low resolution texel falls off with its difference in luminance.
+const GUIDE_SIGMA: f32 = 0.1;
+
+fn luminance(color: Vec3) -> f32 {
+    color.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Joint bilateral upsampling of the lines, when they were computed at a lower resolution.
+fn upsample_dog(source: &Texture, dog: &Texture, uv: Vec2, guide: f32) -> Vec4 {
+    let size = dog.size.as_ivec2();
+    let position = uv * size.as_vec2() - 0.5;
+    let base = position.floor().as_ivec2();
+    let f = position - position.floor();
+
+    let mut d = Vec4::ZERO;
+    let mut weight_sum = 0.0;
+    for offset in [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE] {
+        let coords = (base + offset).clamp(IVec2::ZERO, size - 1);
+        let bilinear = (1.0 - f) * (1 - offset).as_vec2() + f * offset.as_vec2();
+        // the full resolution texture sampled where the lab pass sampled it for this texel
+        let texel_uv = (coords.as_vec2() + 0.5) / size.as_vec2();
+        let difference = luminance(source.sample(texel_uv).xyz()) - guide;
+        let similarity = (-(difference * difference) / (2.0 * GUIDE_SIGMA * GUIDE_SIGMA)).exp();
+        // falls back to bilinear filtering, if none of the texels is similar
+        let weight = bilinear.x * bilinear.y * (similarity + 1e-4);
+
+        d += dog.load(coords) * weight;
+        weight_sum += weight;
+    }
+    d / weight_sum
+}
+
+/// Rotates `uv` by `degrees`, `row_vector` picks between `uv * rot` and `rot * uv` of the shader.
+fn rotate(uv: Vec2, degrees: f32, row_vector: bool) -> Vec2 {
+    let (sin, cos) = (degrees * PI / 180.0).sin_cos();
+    if row_vector {
+        Vec2::new(cos * uv.x - sin * uv.y, sin * uv.x + cos * uv.y)
+    } else {
+        Vec2::new(cos * uv.x + sin * uv.y, -sin * uv.x + cos * uv.y)
+    }
+}
+
+/// The procedural strokes are laid out like on a texture of this size.
+const PROCEDURAL_HATCH_SIZE: f32 = 512.0;
+
+/// A pseudo random number in [0, 1) for every stroke.
+fn hash(n: f32) -> f32 {
+    ((n * 12.9898).sin() * 43_758.547).fract()
+}
+
+/// Procedural strokes along the x axis, `texel` is the position in texels of the virtual texture
+/// and `footprint` how many of them a pixel covers across the strokes.
+fn procedural_hatch(settings: &DoGSettings, texel: Vec2, footprint: f32, layer: usize) -> f32 {
+    let spacing = settings.hatch_spacings[layer].max(1e-3);
+    let half_thickness = settings.hatch_thicknesses[layer] * 0.5;
+    let row = texel.y / spacing;
+
+    // the strokes can be shifted into the neighbouring rows, so those are checked as well
+    let distance = [-1.0, 0.0, 1.0]
+        .into_iter()
+        .map(|offset| {
+            let stroke = row.floor() + offset;
+            let seed = hash(stroke + layer as f32 * 101.0);
+            // every stroke is shifted by its own amount and bends along its length
+            let center = stroke
+                + 0.5
+                + (seed - 0.5) * settings.hatch_jitters[layer]
+                + ((texel.x / (spacing * 8.0) + seed) * 2.0 * PI).sin()
+                    * settings.hatch_wobbles[layer];
+            (row - center).abs() * spacing
+        })
+        .fold(1e6, f32::min);
+
+    // antialiased over the footprint of the pixel
+    let half_footprint = footprint.max(1e-4) * 0.5;
+    let coverage = 1.0
+        - smoothstep(
+            half_thickness - half_footprint,
+            half_thickness + half_footprint,
+            distance,
+        );
+    // strokes that are closer together than a pixel fade to their average coverage
+    let average = (half_thickness * 2.0 / spacing).clamp(0.0, 1.0);
+    let fade = (footprint / spacing * 2.0 - 1.0).clamp(0.0, 1.0);
+    1.0 - (coverage + (average - coverage) * fade)
+}
+
+fn blend_pass(
+    source: &Texture,
+    dog: &Texture,
+    hatches: [&MipChain; 4],
+    settings: &DoGSettings,
+    uv: Vec2,
+) -> Vec4 {
+    let main = source.sample(uv);
+    let d = if dog.size != source.size {
+        upsample_dog(source, dog, uv, luminance(main.xyz()))
+    } else {
+        dog.sample(uv)
+    } * settings.dog_strength;
+    let col = main.xyz();
+    let (min_color, max_color) = (settings.min_color, settings.max_color);
+
+    // the shader computes a color for the blend mode here, but the layers always overwrite it
+    let mut output = Vec3::ONE;
+    let layers = settings.enable_layers;
+    if settings.enable_hatch {
+        // pixels from the center, the scale factor of an image is 1
+        let hatch_uv = (uv - 0.5) * source.size.as_vec2();
+        // the shader rotates the first layer the other way
+        let sample_layer =
+            |layer: usize| sample_hatch(hatches[layer], settings, hatch_uv, layer, layer == 0);
+
+        if layers.x == 1.0 {
+            output = sample_layer(0).lerp(max_color, d.x);
+        }
+        // every enabled layer will just add the respective hatching texture to the output
+        for (layer, enabled, d) in [(1, layers.y, d.y), (2, layers.z, d.z), (3, layers.w, d.w)] {
+            if enabled != 0.0 {
+                output *= sample_layer(layer).lerp(max_color, d);
+            }
+        }
+    } else {
+        if layers.x == 1.0 {
+            output = min_color.lerp(max_color, d.x);
+        }
+        for (enabled, d) in [(layers.y, d.y), (layers.z, d.z), (layers.w, d.w)] {
+            if enabled != 0.0 {
+                output *= min_color.lerp(max_color, d);
+            }
+        }
+    }
+
+    // the view target clamps the values above 1, unless it is hdr
+    col.lerp(output, settings.blend_strength)
+        .extend(1.0)
+        .max(Vec4::ZERO)
+}
+
+/// Samples the hatching of a layer at `hatch_uv`, the pixels from the center of the image.
+/// `reversed` rotates it the other way.
+fn sample_hatch(
+    hatch: &MipChain,
+    settings: &DoGSettings,
+    hatch_uv: Vec2,
+    layer: usize,
+    reversed: bool,
+) -> Vec3 {
+    let resolution = settings.hatch_resolutions[layer];
+    let uv = rotate(hatch_uv, settings.hatch_rotations[layer], reversed);
+    if settings.procedural_hatch {
+        let uv = uv * resolution / PROCEDURAL_HATCH_SIZE + 0.5;
+        // a pixel covers `resolution` texels across the strokes, whatever the rotation
+        let hatch = procedural_hatch(settings, uv * PROCEDURAL_HATCH_SIZE, resolution, layer);
+        return Vec3::splat(hatch);
+    }
+    // a pixel covers `resolution` texels in every direction
+    hatch
+        .sample_grad(uv * resolution / hatch.size().as_vec2() + 0.5, resolution)
+        .xyz()
+}
+
+/// The blend pass of a [`HatchingMode::TonalArtMap`], `tones` from the lightest to the darkest.
+fn tonal_art_map_blend_pass(
+    source: &Texture,
+    dog: &Texture,
+    tones: [&MipChain; 4],
+    settings: &DoGSettings,
+    uv: Vec2,
+) -> Vec4 {
+    if !settings.enable_hatch {
+        return blend_pass(source, dog, tones, settings, uv);
+    }
+    let main = source.sample(uv);
+    let d = if dog.size != source.size {
+        upsample_dog(source, dog, uv, luminance(main.xyz()))
+    } else {
+        dog.sample(uv)
+    } * settings.dog_strength;
+    let col = main.xyz();
+    let (min_color, max_color) = (settings.min_color, settings.max_color);
+
+    // the tones fade in one after the other from light to dark, each on top of the lighter ones
+    let hatch_uv = (uv - 0.5) * source.size.as_vec2();
+    // colors brighter than white, e.g. on hdr cameras, get no strokes from any tone
+    let darkness = (1.0 - luminance(col).max(0.0)) * 4.0;
+    let tone = (0..4)
+        .map(|layer| {
+            let coverage = (darkness - layer as f32).clamp(0.0, 1.0);
+            Vec3::ONE.lerp(
+                sample_hatch(tones[layer], settings, hatch_uv, layer, false),
+                coverage,
+            )
+        })
+        .product::<Vec3>();
+    // the shader mixes every channel on its own
+    let mut output = min_color + (max_color - min_color) * tone;
+
+    // the lines of every enabled layer are drawn on top of the tone
+    let layers = settings.enable_layers;
+    if layers.x == 1.0 {
+        output *= min_color.lerp(max_color, d.x);
+    }
+    for (enabled, d) in [(layers.y, d.y), (layers.z, d.z), (layers.w, d.w)] {
+        if enabled != 0.0 {
+            output *= min_color.lerp(max_color, d);
+        }
+    }
+
+    // the view target clamps the values above 1, unless it is hdr
+    col.lerp(output, settings.blend_strength)
+        .extend(1.0)
+        .max(Vec4::ZERO)
+}
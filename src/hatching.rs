@@ -0,0 +1,543 @@
+use crate::plugin::CROSSHATCH_TEXTURE_HANDLES;
+use bevy::{
+    color::{ColorToComponents, ColorToPacked},
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass},
+    platform::collections::HashSet,
+    prelude::*,
+    render::{extract_component::ExtractComponent, render_resource::TextureFormat},
+};
+
+/// The textures the hatch layers of a camera are drawn with, one for every layer of
+/// [`DoGSettings::enable_layers`](crate::settings::DoGSettings::enable_layers), or the tones of a
+/// tonal art map, see [`HatchingMode`].
+///
+/// Cameras without this component draw like [`DoGHatching::default`], with the bundled crosshatch
+/// texture of every layer.
+/// A layer whose image isn't loaded yet is drawn with the bundled texture of that layer, the
+/// bind group is rebuilt once it is loaded and whenever a handle changes.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_dog::{hatching::DoGHatching, settings::DoGSettings};
+/// fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     commands.spawn((
+///         Camera3d::default(),
+///         DoGSettings::CROSSHATCH,
+///         DoGHatching::default().with_layer(0, asset_server.load("textures/pencil.png")),
+///     ));
+/// }
+/// ```
+#[derive(Component, Reflect, Debug, Clone, PartialEq, ExtractComponent)]
+#[reflect(Component, Default)]
+pub struct DoGHatching {
+    /// The images are sampled with repeat, so they should tile.
+    pub layers: [Handle<Image>; 4],
+    pub mode: HatchingMode,
+    pub space: HatchingSpace,
+}
+
+/// How the hatching textures are picked for a pixel.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HatchingMode {
+    /// Every layer of lines is drawn with its own texture, where the DoG of that layer is dark.
+    #[default]
+    Layers,
+    /// The textures are the tones of a tonal art map, from the lightest to the darkest. Every
+    /// tone fades in on top of the lighter ones as the image gets darker, so dark regions get
+    /// denser strokes. Each tone uses the rotation and resolution of its layer, and the lines of
+    /// every enabled layer are drawn on top.
+    ///
+    /// Like in a tonal art map, every mip level keeps the stroke width in texels and the tone of
+    /// the image, with half the strokes of the level above, so minified strokes stay as wide on
+    /// the screen instead of blurring into grey. The tones that are 8 bit RGBA images without mips,
+    /// like the bundled textures, are drawn with copies that have these mips once they are
+    /// loaded, see [`TonalArtMapTones`]. The strokes have to run along the rows of the images for
+    /// this.
+    TonalArtMap,
+}
+
+/// What the hatching is anchored to.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HatchingSpace {
+    /// The strokes stay in place on the screen and slide over surfaces when the camera or the
+    /// objects move.
+    #[default]
+    Screen,
+    /// The strokes stick to the surfaces. Every surface is hatched with a triplanar mapping of its
+    /// world position, which is rebuilt from the depth and normal prepass, and
+    /// [`DoGSettings::world_hatch_scale`](crate::settings::DoGSettings::world_hatch_scale) sets
+    /// the repeats of a texture per world unit.
+    ///
+    /// The prepasses are added to the camera, so this only works for 3d cameras, others and the
+    /// background are hatched in screen space.
+    World,
+}
+
+impl DoGHatching {
+    pub fn new(layers: [Handle<Image>; 4]) -> Self {
+        DoGHatching {
+            layers,
+            mode: HatchingMode::Layers,
+            space: HatchingSpace::Screen,
+        }
+    }
+
+    /// A tonal art map of four tones, from the lightest to the darkest.
+    pub fn tonal_art_map(tones: [Handle<Image>; 4]) -> Self {
+        DoGHatching {
+            mode: HatchingMode::TonalArtMap,
+            ..DoGHatching::new(tones)
+        }
+    }
+
+    /// Draws every layer with the same image.
+    pub fn single(image: Handle<Image>) -> Self {
+        DoGHatching::new([image.clone(), image.clone(), image.clone(), image])
+    }
+
+    pub fn with_mode(mut self, mode: HatchingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_space(mut self, space: HatchingSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Replaces the image of one layer, `layer` is 0 to 3.
+    pub fn with_layer(mut self, layer: usize, image: Handle<Image>) -> Self {
+        self.layers[layer] = image;
+        self
+    }
+}
+
+/// The four bundled crosshatch textures, one per layer.
+impl Default for DoGHatching {
+    fn default() -> Self {
+        DoGHatching::new(CROSSHATCH_TEXTURE_HANDLES)
+    }
+}
+
+/// The tones a [`HatchingMode::TonalArtMap`] is drawn with, copies of the layers of the
+/// [`DoGHatching`] of the camera with stroke mips.
+///
+/// [`add_tonal_art_map_mips`] adds this to the cameras and rebuilds a tone when its layer changes
+/// or is reloaded. The images of the layers stay as they are, so sprites and materials that use
+/// them don't change.
+#[derive(Component, Debug, Clone, Default, ExtractComponent)]
+pub struct TonalArtMapTones {
+    /// The layer every tone is built from.
+    sources: [AssetId<Image>; 4],
+    /// The layer itself as long as it isn't loaded or doesn't take stroke mips.
+    pub tones: [Handle<Image>; 4],
+}
+
+impl TonalArtMapTones {
+    fn update(
+        &mut self,
+        layers: &[Handle<Image>; 4],
+        images: &mut Assets<Image>,
+        modified: &HashSet<AssetId<Image>>,
+    ) {
+        for (layer, source) in layers.iter().enumerate() {
+            if self.sources[layer] != source.id() {
+                self.sources[layer] = source.id();
+                self.tones[layer] = source.clone();
+            }
+            let built = self.tones[layer].id() != source.id();
+            if built && !modified.contains(&source.id()) {
+                continue;
+            }
+            let Some(image) = images.get(source) else {
+                continue;
+            };
+            if !takes_stroke_mips(image) {
+                self.tones[layer] = source.clone();
+                continue;
+            }
+            let mut tone = image.clone();
+            add_stroke_mips(&mut tone);
+            if built {
+                images.insert(&self.tones[layer], tone);
+            } else {
+                self.tones[layer] = images.add(tone);
+            }
+        }
+    }
+}
+
+/// The 3d cameras whose hatching changed.
+type HatchingCameras<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static DoGHatching,
+        Has<DepthPrepass>,
+        Has<NormalPrepass>,
+    ),
+    (Changed<DoGHatching>, With<Camera3d>),
+>;
+
+/// Adds the prepasses world space hatching reads to the 3d cameras that use it.
+pub fn add_hatching_prepasses(mut commands: Commands, cameras: HatchingCameras) {
+    for (entity, hatching, depth_prepass, normal_prepass) in &cameras {
+        if hatching.space != HatchingSpace::World {
+            continue;
+        }
+        let mut camera = commands.entity(entity);
+        if !depth_prepass {
+            camera.insert(DepthPrepass);
+        }
+        if !normal_prepass {
+            camera.insert(NormalPrepass);
+        }
+    }
+}
+
+/// The fewest rows a mip level is built from strokes with, smaller levels average the level
+/// above.
+const MIN_STROKE_ROWS: u32 = 16;
+/// The fewest strokes a mip level is built from.
+const MIN_STROKES: usize = 4;
+/// How much less ink the rows between the strokes may have than the strokes at most. Images
+/// whose rows are about equally dark don't have strokes along them.
+const STROKE_CONTRAST: f32 = 0.5;
+/// The log2 of the largest and smallest power the ink of a level is raised to, to match the tone
+/// of the level above.
+const TONE_EXPONENT_RANGE: f32 = 4.0;
+/// The steps of the search for that power.
+const TONE_ITERATIONS: usize = 16;
+
+/// Adds the mip chain of a tonal art map tone to an 8 bit RGBA image with a single mip level,
+/// returns if the image had the right format.
+///
+/// Every level keeps every other stroke of the level above, which are the bands of rows between
+/// the rows with the least ink. A kept stroke covers twice as much of the image, so it keeps its
+/// width in texels, and the tone of the level is matched to the level above. Mostly dark images
+/// keep their light strokes instead. Once there are too few strokes the texels are averaged.
+pub fn add_stroke_mips(image: &mut Image) -> bool {
+    if !takes_stroke_mips(image) {
+        return false;
+    }
+    let srgb = image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb;
+    let size = image.texture_descriptor.size;
+    let Some(data) = image.data.as_mut() else {
+        return false;
+    };
+
+    let mut level = MipLevel {
+        width: size.width,
+        height: size.height,
+        texels: data
+            .chunks_exact(4)
+            .map(|texel| {
+                let color = Srgba::rgba_u8(texel[0], texel[1], texel[2], texel[3]);
+                if srgb {
+                    LinearRgba::from(color).to_vec4()
+                } else {
+                    color.to_vec4()
+                }
+            })
+            .collect(),
+        light_strokes: false,
+    };
+    level.light_strokes = level.mean_luminance() < 0.5;
+    let mut mip_level_count = 1;
+    while level.width > 1 || level.height > 1 {
+        level = level.strokes().unwrap_or_else(|| level.average());
+        data.extend(level.texels.iter().flat_map(|texel| {
+            if srgb {
+                Srgba::from(LinearRgba::from_vec4(*texel)).to_u8_array()
+            } else {
+                Srgba::from_vec4(*texel).to_u8_array()
+            }
+        }));
+        mip_level_count += 1;
+    }
+    image.texture_descriptor.mip_level_count = mip_level_count;
+    true
+}
+
+/// If [`add_stroke_mips`] can add mips to the image.
+fn takes_stroke_mips(image: &Image) -> bool {
+    let descriptor = &image.texture_descriptor;
+    matches!(
+        descriptor.format,
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+    ) && descriptor.mip_level_count == 1
+        && descriptor.size.depth_or_array_layers == 1
+        && image.data.is_some()
+}
+
+/// A mip level in linear color.
+struct MipLevel {
+    width: u32,
+    height: u32,
+    texels: Vec<Vec4>,
+    /// Mostly dark images have light strokes between the dark ones, which keep their width
+    /// instead.
+    light_strokes: bool,
+}
+
+impl MipLevel {
+    fn texel(&self, x: u32, y: u32) -> Vec4 {
+        self.texels[(y * self.width + x) as usize]
+    }
+
+    /// How much a texel stands out from the paper.
+    fn ink(&self, texel: Vec4) -> f32 {
+        let luminance = LinearRgba::from_vec4(texel).luminance();
+        if self.light_strokes {
+            luminance
+        } else {
+            1.0 - luminance
+        }
+    }
+
+    /// The texel with its ink raised to `power`, blended towards or away from the paper.
+    fn with_ink(&self, texel: Vec4, power: f32) -> Vec4 {
+        let ink = self.ink(texel);
+        let target = ink.powf(power);
+        let (paper, full) = if self.light_strokes {
+            (Vec3::ZERO, Vec3::ONE)
+        } else {
+            (Vec3::ONE, Vec3::ZERO)
+        };
+        let color = if target < ink {
+            texel.truncate().lerp(paper, 1.0 - target / ink)
+        } else {
+            texel
+                .truncate()
+                .lerp(full, (target - ink) / (1.0 - ink).max(f32::EPSILON))
+        };
+        color.extend(texel.w)
+    }
+
+    fn mean_luminance(&self) -> f32 {
+        self.texels
+            .iter()
+            .map(|texel| LinearRgba::from_vec4(*texel).luminance())
+            .sum::<f32>()
+            / self.texels.len() as f32
+    }
+
+    /// The next level, every texel is the average of up to 2x2 texels.
+    fn average(&self) -> MipLevel {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let texels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .map(|(dx, dy)| {
+                        self.texel(
+                            (x * 2 + dx).min(self.width - 1),
+                            (y * 2 + dy).min(self.height - 1),
+                        )
+                    })
+                    .iter()
+                    .sum::<Vec4>()
+                    / 4.0
+            })
+            .collect();
+        MipLevel {
+            width,
+            height,
+            texels,
+            light_strokes: self.light_strokes,
+        }
+    }
+
+    /// The next level with every other stroke, if this level has enough of them.
+    fn strokes(&self) -> Option<MipLevel> {
+        let (width, height) = (self.width, self.height);
+        if width < 2 || height < MIN_STROKE_ROWS {
+            return None;
+        }
+
+        let (strokes, paper) = self.find_strokes()?;
+        let kept: Vec<Stroke> = strokes
+            .iter()
+            .step_by(2)
+            // with an odd count the last stroke would touch the first one
+            .take(strokes.len() / 2)
+            .copied()
+            .collect();
+
+        let (next_width, next_height) = (width / 2, height / 2);
+        let mut texels = Vec::with_capacity((next_width * next_height) as usize);
+        for y in 0..next_height {
+            // the center of the row in the rows of this level
+            let center = 2.0 * y as f32 + 1.0;
+            let source_rows: Vec<u32> = kept
+                .iter()
+                .filter_map(|stroke| stroke.source_row(center, height))
+                .collect();
+            for x in 0..next_width {
+                let texel = source_rows
+                    .iter()
+                    .map(|row| (self.texel(2 * x, *row) + self.texel(2 * x + 1, *row)) / 2.0)
+                    .fold(paper, |darkest, texel| {
+                        if self.ink(texel) > self.ink(darkest) {
+                            texel
+                        } else {
+                            darkest
+                        }
+                    });
+                texels.push(texel);
+            }
+        }
+        let mut next = MipLevel {
+            width: next_width,
+            height: next_height,
+            texels,
+            light_strokes: self.light_strokes,
+        };
+
+        // the kept strokes don't cover exactly half as much, so the ink is raised to the power
+        // that gives the level the tone of this one, which keeps the cores of the strokes
+        let target: f32 = self
+            .texels
+            .iter()
+            .map(|texel| self.ink(*texel))
+            .sum::<f32>()
+            / self.texels.len() as f32;
+        let strokes = next.texels.clone();
+        let (mut low, mut high) = (-TONE_EXPONENT_RANGE, TONE_EXPONENT_RANGE);
+        for _ in 0..TONE_ITERATIONS {
+            let exponent = (low + high) / 2.0;
+            next.texels = strokes
+                .iter()
+                .map(|texel| next.with_ink(*texel, exponent.exp2()))
+                .collect();
+            let ink = next
+                .texels
+                .iter()
+                .map(|texel| next.ink(*texel))
+                .sum::<f32>()
+                / next.texels.len() as f32;
+            if ink > target {
+                low = exponent;
+            } else {
+                high = exponent;
+            }
+        }
+        Some(next)
+    }
+
+    /// The strokes of this level and the color between them, if the rows separate them.
+    fn find_strokes(&self) -> Option<(Vec<Stroke>, Vec4)> {
+        let (width, height) = (self.width, self.height);
+        let row = |y: i64| y.rem_euclid(height as i64) as usize;
+        let row_ink: Vec<f32> = (0..height)
+            .map(|y| (0..width).map(|x| self.ink(self.texel(x, y))).sum::<f32>() / width as f32)
+            .collect();
+        let smoothed: Vec<f32> = (0..height as i64)
+            .map(|y| (-1..=1).map(|dy| row_ink[row(y + dy)]).sum::<f32>() / 3.0)
+            .collect();
+        let gaps: Vec<i64> = (0..height as i64)
+            .filter(|&y| {
+                let ink = smoothed[y as usize];
+                ink <= smoothed[row(y - 1)] && ink < smoothed[row(y + 1)]
+            })
+            .collect();
+        if gaps.len() < MIN_STROKES {
+            return None;
+        }
+        let gap_ink = gaps.iter().map(|y| smoothed[*y as usize]).sum::<f32>() / gaps.len() as f32;
+        let peak_ink = smoothed.iter().copied().fold(0.0, f32::max);
+        if gap_ink > STROKE_CONTRAST * peak_ink {
+            return None;
+        }
+        let paper = gaps
+            .iter()
+            .flat_map(|y| (0..width).map(|x| self.texel(x, *y as u32)))
+            .sum::<Vec4>()
+            / (gaps.len() as u32 * width) as f32;
+
+        // a stroke spans the rows from the gap before it to the next gap
+        let strokes = (0..gaps.len())
+            .map(|stroke| {
+                let start = gaps[stroke];
+                let end = gaps
+                    .get(stroke + 1)
+                    .copied()
+                    .unwrap_or(gaps[0] + height as i64);
+                let (weight, moment) = (start..end).fold((0.0, 0.0), |(weight, moment), y| {
+                    let ink = row_ink[row(y)];
+                    (weight + ink, moment + ink * (y as f32 + 0.5))
+                });
+                let center = if weight > 0.0 {
+                    moment / weight
+                } else {
+                    (start + end) as f32 / 2.0
+                };
+                Stroke {
+                    start: start as f32,
+                    end: end as f32,
+                    center,
+                }
+            })
+            .collect();
+        Some((strokes, paper))
+    }
+}
+
+/// The rows of a stroke in a mip level, which can reach past the last row.
+#[derive(Clone, Copy)]
+struct Stroke {
+    start: f32,
+    end: f32,
+    center: f32,
+}
+
+impl Stroke {
+    /// The row of the stroke that ends up at `y` once it is twice as tall, which keeps its width
+    /// in the texels of the next level.
+    fn source_row(&self, y: f32, height: u32) -> Option<u32> {
+        let height = height as f32;
+        [-height, 0.0, height]
+            .map(|repeat| y + repeat)
+            .into_iter()
+            .find(|y| (2.0 * self.start - self.center..2.0 * self.end - self.center).contains(y))
+            .map(|y| {
+                (self.center + (y - self.center) / 2.0)
+                    .floor()
+                    .rem_euclid(height) as u32
+            })
+    }
+}
+
+/// Keeps the [`TonalArtMapTones`] of the cameras with a tonal art map up to date, whose tones get
+/// [`add_stroke_mips`] once they are loaded.
+pub fn add_tonal_art_map_mips(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut cameras: Query<(Entity, &DoGHatching, Option<&mut TonalArtMapTones>)>,
+) {
+    let modified: HashSet<_> = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for (entity, hatching, tones) in &mut cameras {
+        if hatching.mode != HatchingMode::TonalArtMap {
+            if tones.is_some() {
+                commands.entity(entity).remove::<TonalArtMapTones>();
+            }
+            continue;
+        }
+        match tones {
+            Some(mut tones) => tones.update(&hatching.layers, &mut images, &modified),
+            None => {
+                let mut tones = TonalArtMapTones::default();
+                tones.update(&hatching.layers, &mut images, &modified);
+                commands.entity(entity).insert(tones);
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! An egui window to tune the effect while the app runs, enabled with the `egui` feature.
+
+use crate::{
+    preset::{DoGPreset, DoGPresetName, DoGPresets},
+    settings::{BlendMode, DoGSettings, GaussianBackend, PassesSettings, Thresholding},
+};
+use bevy::{
+    prelude::*,
+    reflect::{ReflectRef, Struct},
+};
+use bevy_egui::{egui, EguiContextPass, EguiContexts, EguiPlugin};
+use std::path::Path;
+
+/// Adds a window that edits the [`DoGSettings`] and [`PassesSettings`] of the cameras. It can
+/// switch between the [`DoGPresets`], save and load `.dog.ron` files and copy the settings as Rust
+/// code.
+///
+/// Adds the [`EguiPlugin`], if it isn't added yet.
+pub struct DoGInspectorPlugin;
+
+impl Plugin for DoGInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin {
+                enable_multipass_for_primary_context: true,
+            });
+        }
+        app.init_resource::<DoGInspector>()
+            .add_systems(EguiContextPass, inspector_window);
+    }
+}
+
+/// The state of the inspector window.
+#[derive(Resource, Debug, Clone)]
+pub struct DoGInspector {
+    pub open: bool,
+    /// The camera that is edited, the first camera with [`DoGSettings`] if none is chosen.
+    pub camera: Option<Entity>,
+    /// The file that presets are saved to and loaded from.
+    pub preset_path: String,
+    status: String,
+}
+
+impl Default for DoGInspector {
+    fn default() -> Self {
+        DoGInspector {
+            open: true,
+            camera: None,
+            preset_path: "assets/presets/custom.dog.ron".to_string(),
+            status: String::new(),
+        }
+    }
+}
+
+/// Everything of a camera the inspector shows and edits.
+type InspectedCamera = (
+    Entity,
+    Option<&'static Name>,
+    &'static mut DoGSettings,
+    Option<&'static mut PassesSettings>,
+    Option<&'static DoGPresetName>,
+);
+
+fn inspector_window(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<DoGInspector>,
+    mut cameras: Query<InspectedCamera>,
+    presets: Res<DoGPresets>,
+    type_registry: Res<AppTypeRegistry>,
+) {
+    let inspector = &mut *inspector;
+    let mut open = inspector.open;
+    egui::Window::new("DoG Inspector")
+        .vscroll(true)
+        .open(&mut open)
+        .show(contexts.ctx_mut(), |ui| {
+            let names: Vec<_> = cameras
+                .iter()
+                .map(|(entity, name, ..)| {
+                    let name = name.map_or_else(|| entity.to_string(), Name::to_string);
+                    (entity, name)
+                })
+                .collect();
+            let Some((first, _)) = names.first() else {
+                ui.label("No camera has DoGSettings.");
+                return;
+            };
+            let mut camera = inspector
+                .camera
+                .filter(|camera| cameras.contains(*camera))
+                .unwrap_or(*first);
+            if names.len() > 1 {
+                let selected = names.iter().find(|(entity, _)| *entity == camera);
+                egui::ComboBox::from_label("Camera")
+                    .selected_text(selected.map_or("", |(_, name)| name.as_str()))
+                    .show_ui(ui, |ui| {
+                        for (entity, name) in &names {
+                            ui.selectable_value(&mut camera, *entity, name);
+                        }
+                    });
+            }
+            inspector.camera = Some(camera);
+
+            let Ok((_, _, mut settings, mut passes, preset_name)) = cameras.get_mut(camera) else {
+                return;
+            };
+
+            let current = preset_name.map_or("custom", |preset| preset.0.as_str());
+            egui::ComboBox::from_label("Preset")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for name in presets.names() {
+                        if ui.selectable_label(name == current, name).clicked() {
+                            commands.entity(camera).insert(DoGPresetName::new(name));
+                        }
+                    }
+                });
+
+            // edit copies, so the components only change when a value does
+            let mut edited_settings = *settings;
+            let mut edited_passes = passes.as_deref().copied().unwrap_or_default();
+
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut inspector.preset_path);
+            });
+            ui.horizontal(|ui| {
+                let preset = DoGPreset {
+                    settings: edited_settings,
+                    passes: edited_passes,
+                };
+                if ui.button("Save").clicked() {
+                    inspector.status =
+                        match save_preset(&preset, &inspector.preset_path, &type_registry) {
+                            Ok(()) => format!("saved {}", inspector.preset_path),
+                            Err(error) => error,
+                        };
+                }
+                if ui.button("Load").clicked() {
+                    inspector.status = match load_preset(&inspector.preset_path, &type_registry) {
+                        Ok(preset) => {
+                            edited_settings = preset.settings;
+                            edited_passes = preset.passes;
+                            format!("loaded {}", inspector.preset_path)
+                        }
+                        Err(error) => error,
+                    };
+                }
+                if ui.button("Copy as Rust").clicked() {
+                    ui.ctx()
+                        .copy_text(settings_as_rust(&edited_settings, &edited_passes));
+                    inspector.status = "copied the settings".to_string();
+                }
+            });
+            if !inspector.status.is_empty() {
+                ui.label(&inspector.status);
+            }
+
+            ui.style_mut().spacing.slider_width = 100.0;
+            settings_ui(ui, &mut edited_settings, &mut edited_passes);
+
+            if edited_settings.reflect_partial_eq(&*settings) != Some(true) {
+                *settings = edited_settings;
+            }
+            match passes.as_deref_mut() {
+                Some(passes) => {
+                    if edited_passes.reflect_partial_eq(passes) != Some(true) {
+                        *passes = edited_passes;
+                    }
+                }
+                None => {
+                    if edited_passes.reflect_partial_eq(&PassesSettings::default()) != Some(true) {
+                        commands.entity(camera).insert(edited_passes);
+                    }
+                }
+            }
+        });
+    inspector.open = open;
+}
+
+fn save_preset(
+    preset: &DoGPreset,
+    path: &str,
+    type_registry: &AppTypeRegistry,
+) -> Result<(), String> {
+    let ron = preset
+        .to_ron(&type_registry.read())
+        .map_err(|error| error.to_string())?;
+    if let Some(directory) = Path::new(path).parent() {
+        std::fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(path, ron).map_err(|error| format!("can't write {path}: {error}"))
+}
+
+fn load_preset(path: &str, type_registry: &AppTypeRegistry) -> Result<DoGPreset, String> {
+    let source =
+        std::fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    DoGPreset::from_ron(&source, &type_registry.read()).map_err(|error| error.to_string())
+}
+
+/// Shows the controls that matter for the current modes and passes.
+fn settings_ui(ui: &mut egui::Ui, settings: &mut DoGSettings, passes: &mut PassesSettings) {
+    ui.heading("Difference of Gaussians");
+    ui.add(egui::Slider::new(&mut settings.sigma_e, 0.0..=7.0).text("Sigma E"));
+    ui.add(egui::Slider::new(&mut settings.k, 0.1..=5.0).text("K"));
+    ui.add(egui::Slider::new(&mut settings.tau, 0.0..=120.0).text("Tau"));
+    ui.add(egui::Slider::new(&mut settings.dog_strength, 0.0..=5.0).text("DoG Strength"));
+    ui.checkbox(&mut settings.invert, "Invert");
+
+    ui.heading("Thresholding");
+    egui::ComboBox::from_label("Thresholding")
+        .selected_text(format!("{:?}", settings.thresholding))
+        .show_ui(ui, |ui| {
+            for thresholding in [
+                Thresholding::NoThreshold,
+                Thresholding::Tanh,
+                Thresholding::Quantization,
+                Thresholding::SmoothQuantization,
+            ] {
+                ui.selectable_value(
+                    &mut settings.thresholding,
+                    thresholding,
+                    format!("{thresholding:?}"),
+                );
+            }
+        });
+    if settings.thresholding != Thresholding::NoThreshold {
+        ui.add(egui::Slider::new(&mut settings.phi, 0.0..=10.0).text("Phi"));
+    }
+    match settings.thresholding {
+        Thresholding::Tanh => {
+            for (layer, threshold) in settings.thresholds.as_mut().iter_mut().enumerate() {
+                ui.add(
+                    egui::Slider::new(threshold, 0.0..=100.0)
+                        .text(format!("Threshold {}", layer + 1)),
+                );
+            }
+        }
+        Thresholding::Quantization => {
+            ui.add(egui::Slider::new(&mut settings.thresholds.x, 0.0..=100.0).text("Threshold"));
+            ui.add(
+                egui::Slider::new(&mut settings.quantizer_step, 0.0..=5.0).text("Quantizer Step"),
+            );
+        }
+        Thresholding::SmoothQuantization => {
+            ui.add(
+                egui::Slider::new(&mut settings.quantizer_step, 0.0..=5.0).text("Quantizer Step"),
+            );
+        }
+        Thresholding::NoThreshold => {}
+    }
+
+    ui.heading("Blending");
+    egui::ComboBox::from_label("Blend Mode")
+        .selected_text(format!("{:?}", settings.blend_mode))
+        .show_ui(ui, |ui| {
+            for blend_mode in [
+                BlendMode::NoBlend,
+                BlendMode::Interpolate,
+                BlendMode::TwoPointInterpolate,
+            ] {
+                ui.selectable_value(
+                    &mut settings.blend_mode,
+                    blend_mode,
+                    format!("{blend_mode:?}"),
+                );
+            }
+        });
+    ui.add(egui::Slider::new(&mut settings.blend_strength, 0.0..=2.0).text("Blend Strength"));
+    // the hatching replaces the min color with the hatch texture
+    if !settings.enable_hatch {
+        color_edit(ui, &mut settings.min_color, "Min Color");
+    }
+    color_edit(ui, &mut settings.max_color, "Max Color");
+    // the other layers only differ from the first one with tanh thresholding
+    let layers = if settings.thresholding == Thresholding::Tanh {
+        4
+    } else {
+        1
+    };
+    for layer in 0..layers {
+        layer_checkbox(ui, &mut settings.enable_layers[layer], layer);
+    }
+
+    ui.heading("Crosshatch");
+    ui.checkbox(&mut settings.enable_hatch, "Enable Hatch");
+    if settings.enable_hatch {
+        ui.checkbox(&mut settings.procedural_hatch, "Procedural");
+        ui.add(egui::Slider::new(&mut settings.world_hatch_scale, 0.1..=10.0).text("World Scale"));
+        for layer in 0..layers {
+            if settings.enable_layers[layer] == 0.0 {
+                continue;
+            }
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_resolutions[layer], 0.1..=10.0)
+                    .text(format!("Layer {} Resolution", layer + 1)),
+            );
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_rotations[layer], 0.0..=180.0)
+                    .text(format!("Layer {} Rotation", layer + 1)),
+            );
+            // the stroke parameters only shape the procedural hatching
+            if !settings.procedural_hatch {
+                continue;
+            }
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_spacings[layer], 2.0..=64.0)
+                    .text(format!("Layer {} Spacing", layer + 1)),
+            );
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_thicknesses[layer], 0.0..=32.0)
+                    .text(format!("Layer {} Thickness", layer + 1)),
+            );
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_jitters[layer], 0.0..=1.0)
+                    .text(format!("Layer {} Jitter", layer + 1)),
+            );
+            ui.add(
+                egui::Slider::new(&mut settings.hatch_wobbles[layer], 0.0..=1.0)
+                    .text(format!("Layer {} Wobble", layer + 1)),
+            );
+        }
+    }
+
+    ui.heading("Passes");
+    ui.checkbox(&mut passes.fdog, "FDoG");
+    ui.checkbox(&mut passes.anti_aliasing, "Anti Aliasing");
+    if passes.fdog || passes.anti_aliasing {
+        ui.add(egui::Slider::new(&mut settings.sigma_c, 0.0..=7.0).text("Sigma C (Flow Field)"));
+    }
+    if passes.fdog {
+        ui.add(egui::Slider::new(&mut settings.sigma_m, 0.0..=20.0).text("Sigma M"));
+        ui.add(
+            egui::Slider::new(&mut settings.line_conv_step_sizes.x, 0.0..=3.0).text("Line Conv X"),
+        );
+        ui.add(
+            egui::Slider::new(&mut settings.line_conv_step_sizes.y, 0.0..=3.0).text("Line Conv Y"),
+        );
+        ui.checkbox(
+            &mut settings.calc_diff_before_convolution,
+            "Calc Difference before Convolution",
+        );
+    }
+    if passes.anti_aliasing {
+        ui.add(egui::Slider::new(&mut settings.sigma_a, 0.0..=10.0).text("Sigma A"));
+        ui.add(
+            egui::Slider::new(&mut settings.edge_smooth_step_sizes.x, 0.0..=3.0)
+                .text("Edge Smooth X"),
+        );
+        ui.add(
+            egui::Slider::new(&mut settings.edge_smooth_step_sizes.y, 0.0..=3.0)
+                .text("Edge Smooth Y"),
+        );
+    }
+
+    ui.heading("Performance");
+    ui.horizontal(|ui| {
+        ui.label("Gaussian Blurs");
+        ui.radio_value(&mut passes.backend, GaussianBackend::Fragment, "Fragment");
+        ui.radio_value(&mut passes.backend, GaussianBackend::Compute, "Compute");
+    });
+    ui.add(egui::Slider::new(&mut passes.resolution_scale, 0.25..=1.0).text("Resolution Scale"));
+}
+
+fn color_edit(ui: &mut egui::Ui, color: &mut Vec3, label: &str) {
+    ui.horizontal(|ui| {
+        let mut rgb = color.to_array();
+        if ui.color_edit_button_rgb(&mut rgb).changed() {
+            *color = Vec3::from_array(rgb);
+        }
+        ui.label(label);
+    });
+}
+
+/// `enable_layers` holds flags as floats.
+fn layer_checkbox(ui: &mut egui::Ui, enabled: &mut f32, layer: usize) {
+    let mut checked = *enabled != 0.0;
+    if ui
+        .checkbox(&mut checked, format!("Layer {}", layer + 1))
+        .changed()
+    {
+        *enabled = if checked { 1.0 } else { 0.0 };
+    }
+}
+
+/// The settings as Rust code for a camera, with the fields that differ from the defaults:
+///
+/// ```text
+/// (
+///     DoGSettings {
+///         tau: 15.0,
+///         ..default()
+///     },
+///     PassesSettings {
+///         ..default()
+///     },
+/// )
+/// ```
+pub fn settings_as_rust(settings: &DoGSettings, passes: &PassesSettings) -> String {
+    let mut code = "(\n".to_string();
+    for value in [
+        struct_as_rust(settings, &DoGSettings::default()),
+        struct_as_rust(passes, &PassesSettings::default()),
+    ] {
+        for line in value.lines() {
+            code += &format!("    {line}\n");
+        }
+        code.insert(code.len() - 1, ',');
+    }
+    code += ")";
+    code
+}
+
+fn struct_as_rust(value: &dyn Struct, default: &dyn Struct) -> String {
+    let mut code = format!("{} {{\n", value.reflect_short_type_path());
+    for (index, field) in value.iter_fields().enumerate() {
+        let unchanged = default
+            .field_at(index)
+            .and_then(|default| field.reflect_partial_eq(default));
+        if unchanged == Some(true) {
+            continue;
+        }
+        let name = value.name_at(index).unwrap_or_default();
+        code += &format!("    {name}: {},\n", value_as_rust(field));
+    }
+    code += "    ..default()\n}";
+    code
+}
+
+fn value_as_rust(value: &dyn PartialReflect) -> String {
+    if let Some(vector) = value.try_downcast_ref::<Vec2>() {
+        format!("Vec2::new({:?}, {:?})", vector.x, vector.y)
+    } else if let Some(vector) = value.try_downcast_ref::<Vec3>() {
+        format!("Vec3::new({:?}, {:?}, {:?})", vector.x, vector.y, vector.z)
+    } else if let Some(vector) = value.try_downcast_ref::<Vec4>() {
+        format!(
+            "Vec4::new({:?}, {:?}, {:?}, {:?})",
+            vector.x, vector.y, vector.z, vector.w
+        )
+    } else if let ReflectRef::Enum(variant) = value.reflect_ref() {
+        format!(
+            "{}::{}",
+            value.reflect_short_type_path(),
+            variant.variant_name()
+        )
+    } else if let Some(number) = value.try_downcast_ref::<f32>() {
+        format!("{number:?}")
+    } else {
+        format!("{value:?}")
+    }
+}
@@ -1,5 +1,12 @@
+pub mod animation;
+pub mod cpu;
+pub mod hatching;
+#[cfg(feature = "egui")]
+pub mod inspector;
 pub mod node;
 pub mod pipeline;
 pub mod plugin;
+pub mod preset;
 pub mod settings;
 pub mod textures;
+pub mod transition;
@@ -1,10 +1,14 @@
 use crate::{
-    pipeline::{DoGPipelines, GaussianPipelineIDs},
-    plugin::CROSSHATCH_TEXTURE_HANDLE,
-    settings::{DoGSettings, PassesSettings},
-    textures::DoGTextures,
+    hatching::{DoGHatching, TonalArtMapTones},
+    pipeline::{
+        uses_compute_backend, uses_world_space_hatching, DoGPipelines, GaussianPipelineIDs,
+    },
+    plugin::CROSSHATCH_TEXTURE_HANDLES,
+    settings::{DoGSettingsUniform, PassesSettings},
+    textures::{DoGTextures, DoGViews},
 };
 use bevy::{
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass, ViewPrepassTextures},
     ecs::{query::QueryItem, system::Commands, world::World},
     prelude::*,
     render::{
@@ -12,12 +16,14 @@ use bevy::{
         render_asset::RenderAssets,
         render_graph::{NodeRunError, RenderGraphContext, ViewNode},
         render_resource::{
-            AddressMode, BindGroup, BindGroupEntries, FilterMode, Operations, PipelineCache,
-            RenderPassColorAttachment, RenderPassDescriptor, SamplerDescriptor,
+            AddressMode, BindGroup, BindGroupEntries, BufferId, CachedComputePipelineId,
+            ComputePassDescriptor, ComputePipeline, FilterMode, Operations, PipelineCache,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, SamplerDescriptor,
+            TextureView, TextureViewId,
         },
         renderer::{RenderContext, RenderDevice},
         texture::GpuImage,
-        view::{ExtractedView, ViewTarget, ViewUniformOffset, ViewUniforms},
+        view::{ViewTarget, ViewUniformOffset, ViewUniforms},
     },
 };
 
@@ -28,9 +34,8 @@ impl ViewNode for DoGNode {
     type ViewQuery = (
         &'static ViewTarget,
         &'static ViewUniformOffset,
-        &'static DoGSettings,
-        &'static PassesSettings,
-        &'static DynamicUniformIndex<DoGSettings>,
+        &'static DoGSettingsUniform,
+        &'static DynamicUniformIndex<DoGSettingsUniform>,
         &'static GaussianPipelineIDs,
         &'static DoGTextures,
         &'static DoGBindGroups,
@@ -44,7 +49,6 @@ impl ViewNode for DoGNode {
             view_target,
             view_uniform_offset,
             _post_process_settings,
-            passes_settings,
             settings_index,
             view_pipelines,
             textures,
@@ -53,7 +57,6 @@ impl ViewNode for DoGNode {
         world: &World,
     ) -> Result<(), NodeRunError> {
         let pipeline_cache = world.resource::<PipelineCache>();
-        let dog_pipeline = world.resource::<DoGPipelines>();
 
         let _err = pipeline_cache
             .get_render_pipeline_state(view_pipelines.rgb2lab_pipeline_id)
@@ -84,61 +87,36 @@ impl ViewNode for DoGNode {
         }
         */
 
-        let (
-            Some(rgb2lab_pipeline),
-            Some(eigenvector_pipeline),
-            Some(vertical_pipeline),
-            Some(horizontal_pipeline),
-            Some(first_fdog_pipeline),
-            Some(second_fdog_pipeline),
-            Some(first_dog_pipeline),
-            Some(second_dog_pipeline),
-            Some(aa_pipeline),
-            Some(blend_pipeline),
-        ) = (
-            pipeline_cache.get_render_pipeline(view_pipelines.rgb2lab_pipeline_id),
-            pipeline_cache
-                .get_render_pipeline(view_pipelines.tfm_pipeline_ids.eigenvector_pipeline_id),
-            pipeline_cache
-                .get_render_pipeline(view_pipelines.tfm_pipeline_ids.vertical_pipeline_id),
-            pipeline_cache
-                .get_render_pipeline(view_pipelines.tfm_pipeline_ids.horizontal_pipeline_id),
-            pipeline_cache.get_render_pipeline(view_pipelines.fdog_pipeline_ids.first),
-            pipeline_cache.get_render_pipeline(view_pipelines.fdog_pipeline_ids.second),
-            pipeline_cache.get_render_pipeline(view_pipelines.dog_pipeline_ids.first),
-            pipeline_cache.get_render_pipeline(view_pipelines.dog_pipeline_ids.second),
-            pipeline_cache.get_render_pipeline(view_pipelines.aa_pipeline_id),
-            pipeline_cache.get_render_pipeline(view_pipelines.blend_pipeline_id),
-        )
+        let Some(DoGNodePipelines {
+            rgb2lab: rgb2lab_pipeline,
+            tfm: tfm_pipelines,
+            fdog: fdog_pipelines,
+            dog: dog_pipelines,
+            aa: aa_pipeline,
+            blend: blend_pipeline,
+            compute: compute_pipelines,
+        }) = DoGNodePipelines::get(pipeline_cache, view_pipelines)
         else {
             println!("Pipeline cache has not prepared the pipelines yet");
             return Ok(());
         };
-        let postprocess = view_target.post_process_write();
-        let (source, destination) = (postprocess.source, postprocess.destination);
-        let view_uniforms = world.resource::<ViewUniforms>();
-        let Some(view_uniforms) = view_uniforms.uniforms.binding() else {
-            println!("view uniforms");
+        // the prepass textures can be missing for a frame after world space hatching is enabled
+        if view_pipelines.world_space_hatching && bind_groups.world_space_bind_group.is_none() {
             return Ok(());
-        };
-        let settings_uniforms = world.resource::<ComponentUniforms<DoGSettings>>();
-        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
-            println!("settings binding");
+        }
+        // the source alternates between the two main textures of the view target. It is resolved
+        // before `post_process_write` flips them, so returning early leaves the frame as it is
+        let Some(source_bind_group) =
+            bind_groups.source_bind_group(view_target.main_texture_view())
+        else {
             return Ok(());
         };
+        let texture_size = textures.lab_texture.texture.size();
+        let (width, height) = (texture_size.width, texture_size.height);
+        let postprocess = view_target.post_process_write();
+        let destination = postprocess.destination;
 
         {
-            let postprocess_bind_group = render_context.render_device().create_bind_group(
-                "post_process_bind_group",
-                &dog_pipeline.rgba2lab.postprocess_bind_group_layout,
-                &BindGroupEntries::sequential((
-                    source,
-                    &dog_pipeline.rgba2lab.sampler,
-                    view_uniforms.clone(),
-                    settings_binding.clone(),
-                )),
-            );
-
             let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
                 label: Some("rgb2lab_pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
@@ -155,32 +133,31 @@ impl ViewNode for DoGNode {
             render_pass.set_render_pipeline(rgb2lab_pipeline);
             render_pass.set_bind_group(
                 0,
-                &postprocess_bind_group,
+                source_bind_group,
                 &[view_uniform_offset.offset, settings_index.index()],
             );
             // render_pass.set_bind_group(1, &view_smaa_bind_groups.edge_detection_bind_group, &[]);
             render_pass.draw(0..3, 0..1);
         }
 
-        if (passes_settings.aa == 1) && (passes_settings.tfm == 1) {
+        if let (
+            Some(tfm_textures),
+            Some(eigen_bind_group),
+            Some(horizontal_bind_group),
+            Some((eigenvector_pipeline, horizontal_pipeline, vertical_pipeline)),
+        ) = (
+            &textures.tfm_textures,
+            &bind_groups.eigen_bind_group,
+            &bind_groups.horizontal_bind_group,
+            tfm_pipelines,
+        ) {
             // PASS 1 Eigenvector
             {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "eigenvector_process_bind_group",
-                    &dog_pipeline.tfm.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.lab_texture.default_view,
-                        &dog_pipeline.tfm.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
-                );
-
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("eigenvector_pass"),
                         color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &textures.eigen_texture.default_view,
+                            view: &tfm_textures.eigen_texture.default_view,
                             resolve_target: None,
                             ops: Operations::default(),
                         })],
@@ -192,31 +169,32 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(eigenvector_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.lab_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
                 // render_pass.set_bind_group(1, &view_smaa_bind_groups.edge_detection_bind_group, &[]);
                 render_pass.draw(0..3, 0..1);
             }
 
-            // PASS 2 Vertical
-            {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "horizontal_process_bind_group",
-                    &dog_pipeline.tfm.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.eigen_texture.default_view,
-                        &dog_pipeline.tfm.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
+            // PASS 2 Horizontal
+            if let (Some(compute_pipeline), Some(output_bind_group)) = (
+                compute_pipelines.tfm_horizontal,
+                &bind_groups.horizontal_output_bind_group,
+            ) {
+                let offsets = [view_uniform_offset.offset, settings_index.index()];
+                compute_blur_pass(
+                    render_context,
+                    "horizontal compute pass",
+                    compute_pipeline,
+                    &[(eigen_bind_group, &offsets), (output_bind_group, &[])],
+                    (width.div_ceil(WORKGROUP_SIZE), height),
                 );
-
+            } else {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("eigenvector_pass"),
                         color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &textures.horizontal_texture.default_view,
+                            view: &tfm_textures.horizontal_texture.default_view,
                             resolve_target: None,
                             ops: Operations::default(),
                         })],
@@ -228,7 +206,7 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(horizontal_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    eigen_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
                 // render_pass.set_bind_group(1, &view_smaa_bind_groups.edge_detection_bind_group, &[]);
@@ -236,23 +214,24 @@ impl ViewNode for DoGNode {
             }
 
             // PASS 3 Vertical + bringing it together
-            {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "horizontal_process_bind_group",
-                    &dog_pipeline.tfm.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.horizontal_texture.default_view,
-                        &dog_pipeline.tfm.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
+            if let (Some(compute_pipeline), Some(output_bind_group)) = (
+                compute_pipelines.tfm_vertical,
+                &bind_groups.vertical_output_bind_group,
+            ) {
+                let offsets = [view_uniform_offset.offset, settings_index.index()];
+                compute_blur_pass(
+                    render_context,
+                    "vertical compute pass",
+                    compute_pipeline,
+                    &[(horizontal_bind_group, &offsets), (output_bind_group, &[])],
+                    (width, height.div_ceil(WORKGROUP_SIZE)),
                 );
-
+            } else {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("vertical_pass"),
                         color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &textures.vertical_texture.default_view,
+                            view: &tfm_textures.vertical_texture.default_view,
                             resolve_target: None,
                             ops: Operations::default(),
                         })],
@@ -264,7 +243,7 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(vertical_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    horizontal_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
                 // render_pass.set_bind_group(1, &view_smaa_bind_groups.edge_detection_bind_group, &[]);
@@ -272,20 +251,27 @@ impl ViewNode for DoGNode {
             }
         }
 
-        if passes_settings.tfm == 1 {
+        if let (Some((first_fdog_pipeline, second_fdog_pipeline)), Some(tfm_bind_group)) =
+            (fdog_pipelines, &bind_groups.tfm_bind_group)
+        {
             // PASS 4 first FDOG blur
-            {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "horizontal_process_bind_group",
-                    &dog_pipeline.fdog.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.lab_texture.default_view,
-                        &dog_pipeline.fdog.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
+            if let (Some(compute_pipeline), Some(output_bind_group)) = (
+                compute_pipelines.fdog_first,
+                &bind_groups.first_dog_output_bind_group,
+            ) {
+                let offsets = [view_uniform_offset.offset, settings_index.index()];
+                compute_blur_pass(
+                    render_context,
+                    "first_fdog compute pass",
+                    compute_pipeline,
+                    &[
+                        (&bind_groups.lab_bind_group, &offsets),
+                        (tfm_bind_group, &[]),
+                        (output_bind_group, &[]),
+                    ],
+                    (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE)),
                 );
-
+            } else {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("first fdog pass"),
@@ -302,26 +288,15 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(first_fdog_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.lab_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
-                render_pass.set_bind_group(1, &bind_groups.tfm_bind_group, &[]);
+                render_pass.set_bind_group(1, tfm_bind_group, &[]);
                 render_pass.draw(0..3, 0..1);
             }
 
             // PASS 5 second FDOG blur
             {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "second_fdog_bind_group",
-                    &dog_pipeline.fdog.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.first_dog_texture.default_view,
-                        &dog_pipeline.fdog.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
-                );
-
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("second fdog pass"),
@@ -338,26 +313,30 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(second_fdog_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.first_dog_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
-                render_pass.set_bind_group(1, &bind_groups.tfm_bind_group, &[]);
+                render_pass.set_bind_group(1, tfm_bind_group, &[]);
                 render_pass.draw(0..3, 0..1);
             }
-        } else {
+        } else if let Some((first_dog_pipeline, second_dog_pipeline)) = dog_pipelines {
             // PASS 4 first DOG blur
-            {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "horizontal_process_bind_group",
-                    &dog_pipeline.dog.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.lab_texture.default_view,
-                        &dog_pipeline.dog.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
+            if let (Some(compute_pipeline), Some(output_bind_group)) = (
+                compute_pipelines.dog_first,
+                &bind_groups.first_dog_output_bind_group,
+            ) {
+                let offsets = [view_uniform_offset.offset, settings_index.index()];
+                compute_blur_pass(
+                    render_context,
+                    "first_dog compute pass",
+                    compute_pipeline,
+                    &[
+                        (&bind_groups.lab_bind_group, &offsets),
+                        (output_bind_group, &[]),
+                    ],
+                    (width.div_ceil(WORKGROUP_SIZE), height),
                 );
-
+            } else {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("first dog pass"),
@@ -374,25 +353,29 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(first_dog_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.lab_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
                 render_pass.draw(0..3, 0..1);
             }
 
             // PASS 5 second DOG blur
-            {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "second_dog_bind_group",
-                    &dog_pipeline.dog.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.first_dog_texture.default_view,
-                        &dog_pipeline.dog.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
+            if let (Some(compute_pipeline), Some(output_bind_group)) = (
+                compute_pipelines.dog_second,
+                &bind_groups.second_dog_output_bind_group,
+            ) {
+                let offsets = [view_uniform_offset.offset, settings_index.index()];
+                compute_blur_pass(
+                    render_context,
+                    "second_dog compute pass",
+                    compute_pipeline,
+                    &[
+                        (&bind_groups.first_dog_bind_group, &offsets),
+                        (output_bind_group, &[]),
+                    ],
+                    (width, height.div_ceil(WORKGROUP_SIZE)),
                 );
-
+            } else {
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("second dog pass"),
@@ -409,32 +392,25 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(second_dog_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.first_dog_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
                 render_pass.draw(0..3, 0..1);
             }
         }
 
-        if passes_settings.aa == 1 {
+        if let (Some(aa_pipeline), Some(aa_texture), Some(tfm_bind_group)) = (
+            aa_pipeline,
+            &textures.aa_texture,
+            &bind_groups.tfm_bind_group,
+        ) {
             // PASS 6 AA
             {
-                let postprocess_bind_group = render_context.render_device().create_bind_group(
-                    "aa_bind_group",
-                    &dog_pipeline.aa.postprocess_bind_group_layout,
-                    &BindGroupEntries::sequential((
-                        &textures.second_dog_texture.default_view,
-                        &dog_pipeline.aa.sampler,
-                        view_uniforms.clone(),
-                        settings_binding.clone(),
-                    )),
-                );
-
                 let mut render_pass =
                     render_context.begin_tracked_render_pass(RenderPassDescriptor {
                         label: Some("aa pass"),
                         color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &textures.aa_texture.default_view,
+                            view: &aa_texture.default_view,
                             resolve_target: None,
                             ops: Operations::default(),
                         })],
@@ -446,27 +422,16 @@ impl ViewNode for DoGNode {
                 render_pass.set_render_pipeline(aa_pipeline);
                 render_pass.set_bind_group(
                     0,
-                    &postprocess_bind_group,
+                    &bind_groups.second_dog_bind_group,
                     &[view_uniform_offset.offset, settings_index.index()],
                 );
-                render_pass.set_bind_group(1, &bind_groups.tfm_bind_group, &[]);
+                render_pass.set_bind_group(1, tfm_bind_group, &[]);
                 render_pass.draw(0..3, 0..1);
             }
         }
 
         // FINAL PASS Blend
         {
-            let postprocess_bind_group = render_context.render_device().create_bind_group(
-                "blend_process_bind_group",
-                &dog_pipeline.blend.postprocess_bind_group_layout,
-                &BindGroupEntries::sequential((
-                    source,
-                    &dog_pipeline.blend.sampler,
-                    view_uniforms,
-                    settings_binding.clone(),
-                )),
-            );
-
             let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
                 label: Some("blend_pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
@@ -482,14 +447,17 @@ impl ViewNode for DoGNode {
             render_pass.set_render_pipeline(blend_pipeline);
             render_pass.set_bind_group(
                 0,
-                &postprocess_bind_group,
+                source_bind_group,
                 &[view_uniform_offset.offset, settings_index.index()],
             );
-            if passes_settings.aa == 1 {
-                render_pass.set_bind_group(1, &bind_groups.aa_blend_bind_group, &[]);
+            if let Some(aa_blend_bind_group) = &bind_groups.aa_blend_bind_group {
+                render_pass.set_bind_group(1, aa_blend_bind_group, &[]);
             } else {
                 render_pass.set_bind_group(1, &bind_groups.blend_bind_group, &[]);
             }
+            if let Some(world_space_bind_group) = &bind_groups.world_space_bind_group {
+                render_pass.set_bind_group(2, world_space_bind_group, &[]);
+            }
             render_pass.draw(0..3, 0..1);
         }
 
@@ -499,31 +467,272 @@ impl ViewNode for DoGNode {
 
 // fn rgb2lab_render_pass() {}
 
+/// Workgroup size of the separable compute blurs, see `gaussian_compute.wgsl`
+const WORKGROUP_SIZE: u32 = 128;
+/// Workgroup size in each dimension of the FDoG gradient blur, see `fdog.wgsl`
+const TILE_SIZE: u32 = 16;
+
+/// The pipelines of one view, passes that are disabled are `None`.
+struct DoGNodePipelines<'a> {
+    rgb2lab: &'a RenderPipeline,
+    /// eigenvector, horizontal and vertical pass
+    tfm: Option<(&'a RenderPipeline, &'a RenderPipeline, &'a RenderPipeline)>,
+    fdog: Option<(&'a RenderPipeline, &'a RenderPipeline)>,
+    dog: Option<(&'a RenderPipeline, &'a RenderPipeline)>,
+    aa: Option<&'a RenderPipeline>,
+    blend: &'a RenderPipeline,
+    compute: ComputePipelines<'a>,
+}
+
+/// The compute versions of the separable blurs, `None` for passes that run as fragment shaders.
+struct ComputePipelines<'a> {
+    tfm_horizontal: Option<&'a ComputePipeline>,
+    tfm_vertical: Option<&'a ComputePipeline>,
+    fdog_first: Option<&'a ComputePipeline>,
+    dog_first: Option<&'a ComputePipeline>,
+    dog_second: Option<&'a ComputePipeline>,
+}
+
+impl<'a> DoGNodePipelines<'a> {
+    /// Returns `None` until every pipeline the view needs is ready.
+    fn get(pipeline_cache: &'a PipelineCache, ids: &GaussianPipelineIDs) -> Option<Self> {
+        let render = |id| pipeline_cache.get_render_pipeline(id);
+        let compute = |id: Option<CachedComputePipelineId>| match id {
+            Some(id) => pipeline_cache.get_compute_pipeline(id).map(Some),
+            None => Some(None),
+        };
+
+        let tfm = match &ids.tfm_pipeline_ids {
+            Some(tfm) => Some((
+                render(tfm.eigenvector_pipeline_id)?,
+                render(tfm.horizontal_pipeline_id)?,
+                render(tfm.vertical_pipeline_id)?,
+            )),
+            None => None,
+        };
+        let fdog = match &ids.fdog_pipeline_ids {
+            Some(fdog) => Some((render(fdog.first)?, render(fdog.second)?)),
+            None => None,
+        };
+        let dog = match &ids.dog_pipeline_ids {
+            Some(dog) => Some((render(dog.first)?, render(dog.second)?)),
+            None => None,
+        };
+        let aa = match ids.aa_pipeline_id {
+            Some(aa) => Some(render(aa)?),
+            None => None,
+        };
+
+        let compute_ids = ids.compute_pipeline_ids.as_ref();
+        Some(Self {
+            rgb2lab: render(ids.rgb2lab_pipeline_id)?,
+            tfm,
+            fdog,
+            dog,
+            aa,
+            blend: render(ids.blend_pipeline_id)?,
+            compute: ComputePipelines {
+                tfm_horizontal: compute(compute_ids.and_then(|ids| ids.tfm_horizontal))?,
+                tfm_vertical: compute(compute_ids.and_then(|ids| ids.tfm_vertical))?,
+                fdog_first: compute(compute_ids.and_then(|ids| ids.fdog_first))?,
+                dog_first: compute(compute_ids.and_then(|ids| ids.dog_first))?,
+                dog_second: compute(compute_ids.and_then(|ids| ids.dog_second))?,
+            },
+        })
+    }
+}
+
+/// Dispatches one of the compute blurs, with the bind groups and their dynamic offsets in order.
+fn compute_blur_pass(
+    render_context: &mut RenderContext,
+    label: &str,
+    pipeline: &ComputePipeline,
+    bind_groups: &[(&BindGroup, &[u32])],
+    (x, y): (u32, u32),
+) {
+    let mut compute_pass =
+        render_context
+            .command_encoder()
+            .begin_compute_pass(&ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None,
+            });
+
+    compute_pass.set_pipeline(pipeline);
+    for (index, (bind_group, offsets)) in bind_groups.iter().enumerate() {
+        compute_pass.set_bind_group(index as u32, *bind_group, offsets);
+    }
+    compute_pass.dispatch_workgroups(x, y, 1);
+}
+
 /// A render world component that stores the bind groups necessary to perform
-/// SMAA.
+/// the DoG.
 ///
-/// This is stored on each view.
+/// This is stored on each view and only recreated if one of the textures or buffers it is made of
+/// changes.
 #[derive(Component)]
 pub struct DoGBindGroups {
-    pub tfm_bind_group: BindGroup,
-    pub aa_blend_bind_group: BindGroup,
+    /// The post process bind groups reading either of the two main textures of the view target,
+    /// see `DoGBindGroups::source_bind_group`
+    pub source_bind_groups: [(TextureViewId, BindGroup); 2],
+    /// The post process bind groups reading the intermediate textures
+    pub lab_bind_group: BindGroup,
+    pub eigen_bind_group: Option<BindGroup>,
+    pub horizontal_bind_group: Option<BindGroup>,
+    pub first_dog_bind_group: BindGroup,
+    pub second_dog_bind_group: BindGroup,
+    /// The storage textures of the compute passes, only exist if those run
+    pub horizontal_output_bind_group: Option<BindGroup>,
+    pub vertical_output_bind_group: Option<BindGroup>,
+    pub first_dog_output_bind_group: Option<BindGroup>,
+    pub second_dog_output_bind_group: Option<BindGroup>,
+    /// Only exists if the flow field is needed
+    pub tfm_bind_group: Option<BindGroup>,
+    /// Only exists if the anti aliasing pass runs
+    pub aa_blend_bind_group: Option<BindGroup>,
     pub blend_bind_group: BindGroup,
+    /// The depth and normal prepass, only exists if the hatching is anchored to the world
+    pub world_space_bind_group: Option<BindGroup>,
+    key: DoGBindGroupsKey,
 }
 
+impl DoGBindGroups {
+    /// The post process bind group reading `source`, if it is one of the main textures this was
+    /// prepared for.
+    pub fn source_bind_group(&self, source: &TextureView) -> Option<&BindGroup> {
+        self.source_bind_groups
+            .iter()
+            .find(|(id, _)| *id == source.id())
+            .map(|(_, bind_group)| bind_group)
+    }
+}
+
+/// Everything the bind groups of a view are created from.
+#[derive(PartialEq, Eq)]
+struct DoGBindGroupsKey {
+    texture_views: Vec<TextureViewId>,
+    buffers: [BufferId; 2],
+    compute: bool,
+}
+
+/// Everything of a view its bind groups are created from.
+type BindGroupsViewData = (
+    Entity,
+    &'static ViewTarget,
+    &'static DoGTextures,
+    &'static PassesSettings,
+    &'static Msaa,
+    Option<&'static DoGHatching>,
+    Option<&'static TonalArtMapTones>,
+    Option<&'static ViewPrepassTextures>,
+    Has<DepthPrepass>,
+    Has<NormalPrepass>,
+    Option<&'static DoGBindGroups>,
+);
+
 pub fn prepare_dog_bind_groups(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     dog_pipelines: Res<DoGPipelines>,
     images: Res<RenderAssets<GpuImage>>,
-    view_targets: Query<(Entity, &DoGTextures), (With<ExtractedView>, With<DoGSettings>)>,
+    view_uniforms: Res<ViewUniforms>,
+    settings_uniforms: Res<ComponentUniforms<DoGSettingsUniform>>,
+    view_targets: Query<BindGroupsViewData, DoGViews>,
 ) {
-    // Fetch the two lookup textures. These are bundled in this library.
-    let Some(cross_hatch) = images.get(&CROSSHATCH_TEXTURE_HANDLE) else {
+    // Fetch the crosshatch textures. These are bundled in this library.
+    let [Some(hatch_1), Some(hatch_2), Some(hatch_3), Some(hatch_4)] = CROSSHATCH_TEXTURE_HANDLES
+        .each_ref()
+        .map(|handle| images.get(handle))
+    else {
+        return;
+    };
+    let bundled_hatches = [hatch_1, hatch_2, hatch_3, hatch_4];
+    let (
+        Some(view_uniforms_buffer),
+        Some(view_uniforms_binding),
+        Some(settings_buffer),
+        Some(settings_binding),
+    ) = (
+        view_uniforms.uniforms.buffer(),
+        view_uniforms.uniforms.binding(),
+        settings_uniforms.uniforms().buffer(),
+        settings_uniforms.uniforms().binding(),
+    )
+    else {
         return;
     };
 
     // for every camera with dog
-    for (entity, dog_textures) in &view_targets {
+    for (
+        entity,
+        view_target,
+        dog_textures,
+        passes_settings,
+        msaa,
+        hatching,
+        tones,
+        prepass_textures,
+        depth_prepass,
+        normal_prepass,
+        bind_groups,
+    ) in &view_targets
+    {
+        // layers whose image isn't loaded yet use the bundled texture of the layer, cameras
+        // without a `DoGHatching` use all of them like `DoGHatching::default()`
+        let hatches: [&GpuImage; 4] = match hatching {
+            Some(hatching) => {
+                // a tonal art map draws the copies of the layers with stroke mips
+                let layers = tones.map_or(&hatching.layers, |tones| &tones.tones);
+                std::array::from_fn(|layer| {
+                    images.get(&layers[layer]).unwrap_or(bundled_hatches[layer])
+                })
+            }
+            None => bundled_hatches,
+        };
+        let compute = uses_compute_backend(passes_settings, &dog_pipelines);
+        let sources = [
+            view_target.main_texture_view(),
+            view_target.main_texture_other_view(),
+        ];
+
+        let mut texture_views = vec![
+            sources[0].id(),
+            sources[1].id(),
+            hatches[0].texture_view.id(),
+            hatches[1].texture_view.id(),
+            hatches[2].texture_view.id(),
+            hatches[3].texture_view.id(),
+            dog_textures.lab_texture.default_view.id(),
+            dog_textures.first_dog_texture.default_view.id(),
+            dog_textures.second_dog_texture.default_view.id(),
+        ];
+        if let Some(tfm_textures) = &dog_textures.tfm_textures {
+            texture_views.extend([
+                tfm_textures.eigen_texture.default_view.id(),
+                tfm_textures.horizontal_texture.default_view.id(),
+                tfm_textures.vertical_texture.default_view.id(),
+            ]);
+        }
+        if let Some(aa_texture) = &dog_textures.aa_texture {
+            texture_views.push(aa_texture.default_view.id());
+        }
+        let prepass_views = uses_world_space_hatching(hatching, depth_prepass, normal_prepass)
+            .then_some(prepass_textures)
+            .flatten()
+            .and_then(|textures| Some((textures.depth_view()?, textures.normal_view()?)));
+        if let Some((depth_view, normal_view)) = prepass_views {
+            texture_views.extend([depth_view.id(), normal_view.id()]);
+        }
+        let key = DoGBindGroupsKey {
+            texture_views,
+            buffers: [view_uniforms_buffer.id(), settings_buffer.id()],
+            compute,
+        };
+
+        if bind_groups.is_some_and(|bind_groups| bind_groups.key == key) {
+            continue;
+        }
+
         let cross_hatch_sampler = render_device.create_sampler(&SamplerDescriptor {
             label: Some("common sampler"),
             address_mode_u: AddressMode::Repeat,
@@ -531,6 +740,7 @@ pub fn prepare_dog_bind_groups(
             address_mode_w: AddressMode::ClampToEdge,
             mag_filter: FilterMode::Linear,
             min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
             ..default()
         });
 
@@ -544,35 +754,134 @@ pub fn prepare_dog_bind_groups(
             ..default()
         });
 
-        // these don't change, while the post_process_bind_group does for each pass
-        commands.entity(entity).insert(DoGBindGroups {
-            tfm_bind_group: render_device.create_bind_group(
-                Some("TFM bind group"),
-                &dog_pipelines.fdog.tfm_bind_group_layout,
+        // every pass shares the post process layout and only differs in the texture it reads
+        let postprocess_bind_group = |label: &str, texture: &TextureView| {
+            render_device.create_bind_group(
+                label,
+                &dog_pipelines.rgba2lab.postprocess_bind_group_layout,
                 &BindGroupEntries::sequential((
-                    &dog_textures.vertical_texture.default_view,
-                    &point_clamp_sampler,
+                    texture,
+                    &dog_pipelines.rgba2lab.sampler,
+                    view_uniforms_binding.clone(),
+                    settings_binding.clone(),
                 )),
-            ),
+            )
+        };
+        // the storage textures the compute passes write to, if those run
+        let compute_pipelines = dog_pipelines.compute.as_ref().filter(|_| compute);
+        let output_bind_group = |label: &str, mask: bool, texture: &TextureView| {
+            compute_pipelines.map(|compute_pipelines| {
+                let layout = if mask {
+                    &compute_pipelines.mask_output_bind_group_layout
+                } else {
+                    &compute_pipelines.output_bind_group_layout
+                };
+                render_device.create_bind_group(label, layout, &BindGroupEntries::single(texture))
+            })
+        };
 
-            aa_blend_bind_group: render_device.create_bind_group(
-                Some("blend texture bind group"),
-                &dog_pipelines.blend.blend_bind_group_layout,
-                &BindGroupEntries::sequential((
-                    &dog_textures.aa_texture.default_view,
-                    &cross_hatch.texture_view,
-                    &cross_hatch_sampler,
-                )),
+        let tfm_textures = dog_textures.tfm_textures.as_ref();
+
+        commands.entity(entity).insert(DoGBindGroups {
+            source_bind_groups: sources.map(|source| {
+                (
+                    source.id(),
+                    postprocess_bind_group("source_process_bind_group", source),
+                )
+            }),
+            lab_bind_group: postprocess_bind_group(
+                "lab_process_bind_group",
+                &dog_textures.lab_texture.default_view,
+            ),
+            eigen_bind_group: tfm_textures.map(|tfm_textures| {
+                postprocess_bind_group(
+                    "eigenvector_process_bind_group",
+                    &tfm_textures.eigen_texture.default_view,
+                )
+            }),
+            horizontal_bind_group: tfm_textures.map(|tfm_textures| {
+                postprocess_bind_group(
+                    "horizontal_process_bind_group",
+                    &tfm_textures.horizontal_texture.default_view,
+                )
+            }),
+            first_dog_bind_group: postprocess_bind_group(
+                "first_dog_bind_group",
+                &dog_textures.first_dog_texture.default_view,
             ),
+            second_dog_bind_group: postprocess_bind_group(
+                "second_dog_bind_group",
+                &dog_textures.second_dog_texture.default_view,
+            ),
+            horizontal_output_bind_group: tfm_textures.and_then(|tfm_textures| {
+                output_bind_group(
+                    "horizontal_output_bind_group",
+                    false,
+                    &tfm_textures.horizontal_texture.default_view,
+                )
+            }),
+            vertical_output_bind_group: tfm_textures.and_then(|tfm_textures| {
+                output_bind_group(
+                    "vertical_output_bind_group",
+                    false,
+                    &tfm_textures.vertical_texture.default_view,
+                )
+            }),
+            first_dog_output_bind_group: output_bind_group(
+                "first_dog_output_bind_group",
+                false,
+                &dog_textures.first_dog_texture.default_view,
+            ),
+            second_dog_output_bind_group: output_bind_group(
+                "second_dog_output_bind_group",
+                true,
+                &dog_textures.second_dog_texture.default_view,
+            ),
+            tfm_bind_group: tfm_textures.map(|tfm_textures| {
+                render_device.create_bind_group(
+                    Some("TFM bind group"),
+                    &dog_pipelines.fdog.tfm_bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        &tfm_textures.vertical_texture.default_view,
+                        &point_clamp_sampler,
+                    )),
+                )
+            }),
+            aa_blend_bind_group: dog_textures.aa_texture.as_ref().map(|aa_texture| {
+                render_device.create_bind_group(
+                    Some("blend texture bind group"),
+                    &dog_pipelines.blend.blend_bind_group_layout,
+                    &BindGroupEntries::sequential((
+                        &aa_texture.default_view,
+                        &hatches[0].texture_view,
+                        &hatches[1].texture_view,
+                        &hatches[2].texture_view,
+                        &hatches[3].texture_view,
+                        &cross_hatch_sampler,
+                    )),
+                )
+            }),
             blend_bind_group: render_device.create_bind_group(
                 Some("blend texture bind group"),
                 &dog_pipelines.blend.blend_bind_group_layout,
                 &BindGroupEntries::sequential((
                     &dog_textures.second_dog_texture.default_view,
-                    &cross_hatch.texture_view,
+                    &hatches[0].texture_view,
+                    &hatches[1].texture_view,
+                    &hatches[2].texture_view,
+                    &hatches[3].texture_view,
                     &cross_hatch_sampler,
                 )),
             ),
+            world_space_bind_group: prepass_views.map(|(depth_view, normal_view)| {
+                render_device.create_bind_group(
+                    Some("world space hatching bind group"),
+                    &dog_pipelines.blend.world_space_bind_group_layouts
+                        [(msaa.samples() > 1) as usize],
+                    &BindGroupEntries::sequential((depth_view, normal_view)),
+                )
+            }),
+            key,
         });
     }
 }
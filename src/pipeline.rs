@@ -2,25 +2,39 @@ use super::plugin::{
     AA_SHADER_HANDLE, BLEND_SHADER_HANDLE, DOG_SHADER_HANDLE, FDOG_SHADER_HANDLE,
     RGB2LAB_SHADER_HANDLE, TFM_SHADER_HANDLE,
 };
-use crate::settings::DoGSettings;
+use crate::{
+    hatching::{DoGHatching, HatchingMode, HatchingSpace},
+    settings::{DoGPass, DoGSettingsUniform, GaussianBackend, PassesSettings},
+    textures::{
+        first_dog_texture_format, DoGViews, COMPUTE_TEXTURE_FORMAT, LAB_TEXTURE_FORMAT,
+        MASK_TEXTURE_FORMAT, TENSOR_TEXTURE_FORMAT,
+    },
+};
 use bevy::render::render_resource::{
-    AddressMode, FilterMode, Sampler, SpecializedRenderPipeline, SpecializedRenderPipelines,
+    AddressMode, FilterMode, Sampler, SpecializedComputePipeline, SpecializedComputePipelines,
+    SpecializedRenderPipeline, SpecializedRenderPipelines,
 };
 use bevy::{
-    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    core_pipeline::{
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        prepass::{DepthPrepass, NormalPrepass},
+    },
     ecs::world::{FromWorld, World},
-    image::BevyDefault,
     prelude::*,
     render::{
         render_resource::{
-            binding_types::{sampler, texture_2d, uniform_buffer},
-            BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId, ColorTargetState,
-            ColorWrites, FragmentState, MultisampleState, PipelineCache, PrimitiveState,
-            RenderPipelineDescriptor, SamplerBindingType, SamplerDescriptor, ShaderStages,
-            TextureFormat, TextureSampleType,
+            binding_types::{
+                sampler, texture_2d, texture_2d_multisampled, texture_depth_2d,
+                texture_depth_2d_multisampled, texture_storage_2d, uniform_buffer,
+            },
+            BindGroupLayout, BindGroupLayoutEntries, CachedComputePipelineId,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, ComputePipelineDescriptor,
+            DownlevelFlags, FragmentState, MultisampleState, PipelineCache, PrimitiveState,
+            RenderPipelineDescriptor, SamplerBindingType, SamplerDescriptor, ShaderDefVal,
+            ShaderStages, StorageTextureAccess, TextureFormat, TextureSampleType, WgpuLimits,
         },
-        renderer::RenderDevice,
-        view::ViewUniform,
+        renderer::{RenderAdapter, RenderDevice},
+        view::{ViewTarget, ViewUniform},
     },
 };
 
@@ -39,13 +53,14 @@ pub struct DoGPipelines {
     pub aa: AntiAlliasingPipeline,
     // Pass 7: Blending with potential hatch texture
     pub blend: BlendPipeline,
+    // Compute versions of the separable blurs in passes 2-5, if the device supports them
+    pub compute: Option<GaussianComputePipeline>,
 }
 
 pub struct RGB2LABPipeline {
     /// The bind group layout common to all passes.
     pub postprocess_bind_group_layout: BindGroupLayout,
     pub sampler: Sampler,
-    pub pipeline_id: CachedRenderPipelineId,
 }
 
 pub struct TFMPipeline {
@@ -96,7 +111,7 @@ pub struct AntiAlliasingPipeline {
     pub postprocess_bind_group_layout: BindGroupLayout,
     pub sampler: Sampler,
     /// The bind group layout for data specific to this pass.
-    pub pipeline_id: CachedRenderPipelineId,
+    pub tfm_bind_group_layout: BindGroupLayout,
 }
 
 pub struct BlendPipeline {
@@ -105,7 +120,31 @@ pub struct BlendPipeline {
     pub sampler: Sampler,
     /// The bind group layout for data specific to this pass.
     pub blend_bind_group_layout: BindGroupLayout,
-    pub pipeline_id: CachedRenderPipelineId,
+    /// The bind group layouts for the depth and normal prepass of world space hatching, without
+    /// and with msaa.
+    pub world_space_bind_group_layouts: [BindGroupLayout; 2],
+}
+
+/// Runs the separable gaussian blurs as compute shaders, for `GaussianBackend::Compute`.
+pub struct GaussianComputePipeline {
+    /// The bind group layout common to all passes.
+    pub postprocess_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for the flow field of the FDoG pass.
+    pub tfm_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for the storage texture the passes write to.
+    pub output_bind_group_layout: BindGroupLayout,
+    /// The bind group layout for the thresholded output of the second DoG pass.
+    pub mask_output_bind_group_layout: BindGroupLayout,
+}
+
+/// Like the fragment pipelines, only the ones for the enabled passes are specialized.
+pub struct GaussianComputePipelineIDs {
+    pub tfm_horizontal: Option<CachedComputePipelineId>,
+    pub tfm_vertical: Option<CachedComputePipelineId>,
+    /// The gradient blur of the FDoG, the blur along the flow always is a fragment pass
+    pub fdog_first: Option<CachedComputePipelineId>,
+    pub dog_first: Option<CachedComputePipelineId>,
+    pub dog_second: Option<CachedComputePipelineId>,
 }
 
 /// A render world component that holds the pipeline IDs for all passes needed in this effect.
@@ -117,28 +156,45 @@ pub struct BlendPipeline {
 pub struct GaussianPipelineIDs {
     /// The pipeline ID to turn rgb into lab
     pub rgb2lab_pipeline_id: CachedRenderPipelineId,
-    /// The pipeline IDs for the horizontal and vertical TFM pass
-    pub tfm_pipeline_ids: TFMPipelineIDs,
-    /// The pipeline IDs for the dog passes
-    pub dog_pipeline_ids: DoGPipelineIDs,
-    pub fdog_pipeline_ids: FDoGPipelineIDs,
+    /// The pipeline IDs for the horizontal and vertical TFM pass, if the flow field is needed
+    pub tfm_pipeline_ids: Option<TFMPipelineIDs>,
+    /// The pipeline IDs for the dog passes, if the FDoG is disabled
+    pub dog_pipeline_ids: Option<DoGPipelineIDs>,
+    /// The pipeline IDs for the fdog passes, if enabled
+    pub fdog_pipeline_ids: Option<FDoGPipelineIDs>,
     /// The pipeline ID for optional AA
-    pub aa_pipeline_id: CachedRenderPipelineId,
+    pub aa_pipeline_id: Option<CachedRenderPipelineId>,
     /// The pipeline ID for blending the optional texture
     pub blend_pipeline_id: CachedRenderPipelineId,
+    /// Whether the blend pipeline reads the prepass textures for world space hatching
+    pub world_space_hatching: bool,
+    /// The pipeline IDs for the compute blurs, if this view uses them
+    pub compute_pipeline_ids: Option<GaussianComputePipelineIDs>,
 }
 
 impl FromWorld for DoGPipelines {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
-        let pipeline_cache = world.resource::<PipelineCache>();
+        let render_adapter = world.resource::<RenderAdapter>();
+
+        // WebGL2 has neither compute shaders nor storage textures, wgpu rejects layouts that use
+        // them, so the compute layouts only exist where the compute passes can run
+        let compute = supports_compute_backend(
+            render_adapter.get_downlevel_capabilities().flags,
+            &render_device.limits(),
+        );
+        let visibility = if compute {
+            ShaderStages::FRAGMENT | ShaderStages::COMPUTE
+        } else {
+            ShaderStages::FRAGMENT
+        };
 
         // bind group 1
         // first making one for rgb2lab -> doesn't need any texture to read from
         let postprocess_bind_group_layout = render_device.create_bind_group_layout(
             "DoG postprocess_bind_group_layout",
             &BindGroupLayoutEntries::sequential(
-                ShaderStages::FRAGMENT,
+                visibility,
                 (
                     // source
                     texture_2d(TextureSampleType::Float { filterable: true }),
@@ -146,7 +202,7 @@ impl FromWorld for DoGPipelines {
                     // view
                     uniform_buffer::<ViewUniform>(true),
                     // settings specific to DoG
-                    uniform_buffer::<DoGSettings>(true),
+                    uniform_buffer::<DoGSettingsUniform>(true),
                 ),
             ),
         );
@@ -155,7 +211,7 @@ impl FromWorld for DoGPipelines {
         let tfm_bind_group_layout = render_device.create_bind_group_layout(
             "tfm_bind_group_layout",
             &BindGroupLayoutEntries::sequential(
-                ShaderStages::FRAGMENT,
+                visibility,
                 (
                     // tfm
                     texture_2d(TextureSampleType::Float { filterable: true }),
@@ -174,7 +230,10 @@ impl FromWorld for DoGPipelines {
                 (
                     // dog
                     texture_2d(TextureSampleType::Float { filterable: true }),
-                    // hatch
+                    // hatch, one texture per layer
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
                     texture_2d(TextureSampleType::Float { filterable: true }),
                     // sampler for hatch
                     sampler(SamplerBindingType::Filtering),
@@ -182,26 +241,30 @@ impl FromWorld for DoGPipelines {
             ),
         );
 
-        let pipeline_id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
-            label: Some("rgba2lab_pipeline".into()),
-            layout: vec![postprocess_bind_group_layout.clone()],
-            vertex: fullscreen_shader_vertex_state(),
-            fragment: Some(FragmentState {
-                shader: RGB2LAB_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend: None,
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            push_constant_ranges: vec![],
-            zero_initialize_workgroup_memory: false,
-        });
+        // and one for the prepasses world space hatching reads, whose textures are multisampled
+        // with msaa
+        let world_space_bind_group_layouts = [
+            render_device.create_bind_group_layout(
+                "world_space_hatching_bind_group_layout",
+                &BindGroupLayoutEntries::sequential(
+                    ShaderStages::FRAGMENT,
+                    (
+                        texture_depth_2d(),
+                        texture_2d(TextureSampleType::Float { filterable: false }),
+                    ),
+                ),
+            ),
+            render_device.create_bind_group_layout(
+                "world_space_hatching_multisampled_bind_group_layout",
+                &BindGroupLayoutEntries::sequential(
+                    ShaderStages::FRAGMENT,
+                    (
+                        texture_depth_2d_multisampled(),
+                        texture_2d_multisampled(TextureSampleType::Float { filterable: false }),
+                    ),
+                ),
+            ),
+        ];
 
         let sampler = render_device.create_sampler(&SamplerDescriptor {
             label: Some("common sampler"),
@@ -215,7 +278,6 @@ impl FromWorld for DoGPipelines {
         let rgba2lab = RGB2LABPipeline {
             postprocess_bind_group_layout: postprocess_bind_group_layout.clone(),
             sampler: sampler.clone(),
-            pipeline_id,
         };
 
         let tfm = TFMPipeline {
@@ -234,65 +296,37 @@ impl FromWorld for DoGPipelines {
             sampler: sampler.clone(),
         };
 
-        let pipeline_id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
-            label: Some("anti_aliasing_pipeline".into()),
-            layout: vec![
-                postprocess_bind_group_layout.clone(),
-                tfm_bind_group_layout.clone(),
-            ],
-            vertex: fullscreen_shader_vertex_state(),
-            fragment: Some(FragmentState {
-                shader: AA_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend: None,
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            push_constant_ranges: vec![],
-            zero_initialize_workgroup_memory: false,
-        });
-
         let aa = AntiAlliasingPipeline {
             postprocess_bind_group_layout: postprocess_bind_group_layout.clone(),
             sampler: sampler.clone(),
-            pipeline_id,
+            tfm_bind_group_layout: tfm_bind_group_layout.clone(),
         };
 
-        let pipeline_id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
-            label: Some("blending_pipeline".into()),
-            layout: vec![
-                postprocess_bind_group_layout.clone(),
-                blend_bind_group_layout.clone(),
-            ],
-            vertex: fullscreen_shader_vertex_state(),
-            fragment: Some(FragmentState {
-                shader: BLEND_SHADER_HANDLE,
-                shader_defs: vec![],
-                entry_point: "fragment".into(),
-                targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend: None,
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            push_constant_ranges: vec![],
-            zero_initialize_workgroup_memory: false,
+        // bind groups for the storage textures of the compute passes
+        let compute = compute.then(|| GaussianComputePipeline {
+            postprocess_bind_group_layout: postprocess_bind_group_layout.clone(),
+            tfm_bind_group_layout,
+            output_bind_group_layout: render_device.create_bind_group_layout(
+                "gaussian_compute_output_bind_group_layout",
+                &BindGroupLayoutEntries::single(
+                    ShaderStages::COMPUTE,
+                    texture_storage_2d(COMPUTE_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+            mask_output_bind_group_layout: render_device.create_bind_group_layout(
+                "gaussian_compute_mask_output_bind_group_layout",
+                &BindGroupLayoutEntries::single(
+                    ShaderStages::COMPUTE,
+                    texture_storage_2d(MASK_TEXTURE_FORMAT, StorageTextureAccess::WriteOnly),
+                ),
+            ),
         });
 
         let blend = BlendPipeline {
             postprocess_bind_group_layout,
             sampler,
             blend_bind_group_layout,
-            pipeline_id,
+            world_space_bind_group_layouts,
         };
 
         Self {
@@ -302,39 +336,79 @@ impl FromWorld for DoGPipelines {
             dog,
             aa,
             blend,
+            compute,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct RGB2LABPipelineKeys {
+    /// The format of the texture the lab colors are written to
+    texture_format: TextureFormat,
+}
+
+impl SpecializedRenderPipeline for RGB2LABPipeline {
+    type Key = RGB2LABPipelineKeys;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("rgba2lab_pipeline".into()),
+            layout: vec![self.postprocess_bind_group_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: RGB2LAB_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
         }
     }
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
-pub enum TFMPipelineKeys {
+pub enum TFMPass {
     Eigenvector,
     Vertical,
     Horizontal,
 }
 
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct TFMPipelineKeys {
+    pass: TFMPass,
+    texture_format: TextureFormat,
+}
+
 impl SpecializedRenderPipeline for TFMPipeline {
     type Key = TFMPipelineKeys;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
         // let shader_defs = vec![preset.shader_def()];
 
-        let label = match key {
-            TFMPipelineKeys::Eigenvector => Some("Eigenvector".into()),
-            TFMPipelineKeys::Vertical => Some("VerticalPass".into()),
-            TFMPipelineKeys::Horizontal => Some("HorizontalPass".into()),
+        let label = match key.pass {
+            TFMPass::Eigenvector => Some("Eigenvector".into()),
+            TFMPass::Vertical => Some("VerticalPass".into()),
+            TFMPass::Horizontal => Some("HorizontalPass".into()),
         };
 
-        let entry_point = match key {
-            TFMPipelineKeys::Eigenvector => "calculate_eigenvector".into(),
-            TFMPipelineKeys::Vertical => "vertical_blur_pass".into(),
-            TFMPipelineKeys::Horizontal => "horizontal_blur_pass".into(),
+        let entry_point = match key.pass {
+            TFMPass::Eigenvector => "calculate_eigenvector".into(),
+            TFMPass::Vertical => "vertical_blur_pass".into(),
+            TFMPass::Horizontal => "horizontal_blur_pass".into(),
         };
 
-        let shader_defs = match key {
-            TFMPipelineKeys::Eigenvector => vec!["EIGENVECTOR".into()],
-            TFMPipelineKeys::Vertical => vec!["VERTICAL".into()],
-            TFMPipelineKeys::Horizontal => vec!["HORIZONTAL".into()],
+        let shader_defs = match key.pass {
+            TFMPass::Eigenvector => vec!["EIGENVECTOR".into()],
+            TFMPass::Vertical => vec!["VERTICAL".into()],
+            TFMPass::Horizontal => vec!["HORIZONTAL".into()],
         };
 
         RenderPipelineDescriptor {
@@ -346,7 +420,7 @@ impl SpecializedRenderPipeline for TFMPipeline {
                 shader_defs,
                 entry_point,
                 targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
+                    format: key.texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -363,6 +437,7 @@ impl SpecializedRenderPipeline for TFMPipeline {
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct FDoGPipelineKeys {
     first: bool,
+    texture_format: TextureFormat,
 }
 
 impl SpecializedRenderPipeline for FDoGPipeline {
@@ -394,7 +469,7 @@ impl SpecializedRenderPipeline for FDoGPipeline {
                 shader_defs: vec![],
                 entry_point,
                 targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
+                    format: key.texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -411,6 +486,7 @@ impl SpecializedRenderPipeline for FDoGPipeline {
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct DoGPipelineKeys {
     first: bool,
+    texture_format: TextureFormat,
 }
 
 impl SpecializedRenderPipeline for DoGPipeline {
@@ -437,7 +513,42 @@ impl SpecializedRenderPipeline for DoGPipeline {
                 shader_defs: vec![],
                 entry_point,
                 targets: vec![Some(ColorTargetState {
-                    format: TextureFormat::bevy_default(),
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct AntiAlliasingPipelineKeys {
+    texture_format: TextureFormat,
+}
+
+impl SpecializedRenderPipeline for AntiAlliasingPipeline {
+    type Key = AntiAlliasingPipelineKeys;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("anti_aliasing_pipeline".into()),
+            layout: vec![
+                self.postprocess_bind_group_layout.clone(),
+                self.tfm_bind_group_layout.clone(),
+            ],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: AA_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -451,85 +562,355 @@ impl SpecializedRenderPipeline for DoGPipeline {
     }
 }
 
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct BlendPipelineKeys {
+    /// The format of the view target, which is the HDR format if the camera has hdr enabled
+    texture_format: TextureFormat,
+    /// Whether the hatching textures are a tonal art map, see `HatchingMode::TonalArtMap`
+    tonal_art_map: bool,
+    /// Whether the hatching is anchored to the world, see `HatchingSpace::World`
+    world_space: bool,
+    /// Whether the prepass textures of world space hatching are multisampled
+    multisampled: bool,
+}
+
+impl BlendPipelineKeys {
+    /// Whether the view target keeps values above 1 for the tonemapping.
+    fn hdr(&self) -> bool {
+        self.texture_format == ViewTarget::TEXTURE_FORMAT_HDR
+    }
+}
+
+/// The shader defs of the blend shader for the flags of the [`BlendPipelineKeys`].
+pub fn blend_shader_defs(
+    tonal_art_map: bool,
+    world_space: bool,
+    multisampled: bool,
+    hdr: bool,
+) -> Vec<ShaderDefVal> {
+    let mut shader_defs = vec![];
+    if hdr {
+        shader_defs.push("HDR".into());
+    }
+    if tonal_art_map {
+        shader_defs.push("TONAL_ART_MAP".into());
+    }
+    // the prepass textures are only read for world space hatching
+    if world_space {
+        shader_defs.push("WORLD_SPACE_HATCHING".into());
+        if multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+    }
+    shader_defs
+}
+
+impl SpecializedRenderPipeline for BlendPipeline {
+    type Key = BlendPipelineKeys;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut layout = vec![
+            self.postprocess_bind_group_layout.clone(),
+            self.blend_bind_group_layout.clone(),
+        ];
+        if key.world_space {
+            layout.push(self.world_space_bind_group_layouts[key.multisampled as usize].clone());
+        }
+        let shader_defs = blend_shader_defs(
+            key.tonal_art_map,
+            key.world_space,
+            key.multisampled,
+            key.hdr(),
+        );
+
+        RenderPipelineDescriptor {
+            label: Some("blending_pipeline".into()),
+            layout,
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: BLEND_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub enum GaussianComputePipelineKeys {
+    TFMHorizontal,
+    TFMVertical,
+    FDoGFirst,
+    DoGFirst,
+    DoGSecond,
+}
+
+impl SpecializedComputePipeline for GaussianComputePipeline {
+    type Key = GaussianComputePipelineKeys;
+
+    fn specialize(&self, key: Self::Key) -> ComputePipelineDescriptor {
+        let (label, shader, entry_point) = match key {
+            GaussianComputePipelineKeys::TFMHorizontal => (
+                "horizontal tfm blur compute pass",
+                TFM_SHADER_HANDLE,
+                "horizontal_blur_compute",
+            ),
+            GaussianComputePipelineKeys::TFMVertical => (
+                "vertical tfm blur compute pass",
+                TFM_SHADER_HANDLE,
+                "vertical_blur_compute",
+            ),
+            GaussianComputePipelineKeys::FDoGFirst => (
+                "first fdog blur compute pass",
+                FDOG_SHADER_HANDLE,
+                "fdog_blur_compute",
+            ),
+            GaussianComputePipelineKeys::DoGFirst => (
+                "first dog blur compute pass",
+                DOG_SHADER_HANDLE,
+                "first_gaussian_blur_compute",
+            ),
+            GaussianComputePipelineKeys::DoGSecond => (
+                "second dog blur compute pass with difference",
+                DOG_SHADER_HANDLE,
+                "second_gaussian_blur_compute",
+            ),
+        };
+
+        let mut shader_defs = vec!["COMPUTE".into()];
+        // the fdog pass additionally reads the flow field in group 1
+        let layout = match key {
+            GaussianComputePipelineKeys::FDoGFirst => vec![
+                self.postprocess_bind_group_layout.clone(),
+                self.tfm_bind_group_layout.clone(),
+                self.output_bind_group_layout.clone(),
+            ],
+            GaussianComputePipelineKeys::DoGSecond => {
+                shader_defs.push("MASK_OUTPUT".into());
+                vec![
+                    self.postprocess_bind_group_layout.clone(),
+                    self.mask_output_bind_group_layout.clone(),
+                ]
+            }
+            _ => vec![
+                self.postprocess_bind_group_layout.clone(),
+                self.output_bind_group_layout.clone(),
+            ],
+        };
+
+        ComputePipelineDescriptor {
+            label: Some(label.into()),
+            layout,
+            push_constant_ranges: vec![],
+            shader,
+            shader_defs,
+            entry_point: entry_point.into(),
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+/// Whether the hatching of this view is anchored to the world, which needs the depth and normal
+/// prepass. Views without them are hatched in screen space.
+pub fn uses_world_space_hatching(
+    hatching: Option<&DoGHatching>,
+    depth_prepass: bool,
+    normal_prepass: bool,
+) -> bool {
+    hatching.is_some_and(|hatching| hatching.space == HatchingSpace::World)
+        && depth_prepass
+        && normal_prepass
+}
+
+/// Whether a device with these capabilities can run the compute blurs. Those need compute
+/// shaders, storage textures to write to and workgroups of 128 threads, which e.g. WebGL2
+/// doesn't have.
+pub fn supports_compute_backend(downlevel_flags: DownlevelFlags, limits: &WgpuLimits) -> bool {
+    downlevel_flags.contains(DownlevelFlags::COMPUTE_SHADERS)
+        && limits.max_storage_textures_per_shader_stage > 0
+        && limits.max_compute_invocations_per_workgroup >= 256
+        && limits.max_compute_workgroup_size_x >= 128
+        && limits.max_compute_workgroup_size_y >= 128
+        && limits.max_compute_workgroup_storage_size >= 10_000
+}
+
+/// Whether the gaussian blurs of this view run as compute passes.
+///
+/// Falls back to the fragment passes on devices which can't run the compute shaders, see
+/// [`supports_compute_backend`].
+pub fn uses_compute_backend(passes: &PassesSettings, dog_pipelines: &DoGPipelines) -> bool {
+    passes.backend == GaussianBackend::Compute && dog_pipelines.compute.is_some()
+}
+
 #[derive(Resource, Default)]
 pub struct DoGSpecializedRenderPipelines {
+    rgba2lab: SpecializedRenderPipelines<RGB2LABPipeline>,
     tfm: SpecializedRenderPipelines<TFMPipeline>,
     fdog: SpecializedRenderPipelines<FDoGPipeline>,
     dog: SpecializedRenderPipelines<DoGPipeline>,
+    aa: SpecializedRenderPipelines<AntiAlliasingPipeline>,
+    blend: SpecializedRenderPipelines<BlendPipeline>,
+    compute: SpecializedComputePipelines<GaussianComputePipeline>,
 }
 
+/// Everything of a view its pipelines are specialized on.
+type PipelinesViewData = (
+    Entity,
+    &'static ViewTarget,
+    &'static PassesSettings,
+    &'static Msaa,
+    Option<&'static DoGHatching>,
+    Has<DepthPrepass>,
+    Has<NormalPrepass>,
+);
+
 pub fn prepare_gaussian_pipelines(
     mut commands: Commands,
     pipeline_cache: Res<PipelineCache>,
     dog_pipelines: Res<DoGPipelines>,
     mut specialized_render_pipelines: ResMut<DoGSpecializedRenderPipelines>,
-    views: Query<(Entity, &DoGSettings)>,
+    views: Query<PipelinesViewData, DoGViews>,
 ) {
-    for (entity, _dog) in &views {
-        let rgb2lab_pipeline_id = dog_pipelines.rgba2lab.pipeline_id;
-
-        let eigenvector_pipeline_id = specialized_render_pipelines.tfm.specialize(
-            &pipeline_cache,
-            &dog_pipelines.tfm,
-            TFMPipelineKeys::Eigenvector,
-        );
-
-        let vertical_pipeline_id = specialized_render_pipelines.tfm.specialize(
+    for (entity, view_target, passes_settings, msaa, hatching, depth_prepass, normal_prepass) in
+        &views
+    {
+        let passes = passes_settings.passes();
+        let compute = uses_compute_backend(passes_settings, &dog_pipelines);
+        // the intermediates have their own formats, see `prepare_dog_textures`
+        let first_dog_format = first_dog_texture_format(passes, compute);
+
+        let rgb2lab_pipeline_id = specialized_render_pipelines.rgba2lab.specialize(
             &pipeline_cache,
-            &dog_pipelines.tfm,
-            TFMPipelineKeys::Vertical,
+            &dog_pipelines.rgba2lab,
+            RGB2LABPipelineKeys {
+                texture_format: LAB_TEXTURE_FORMAT,
+            },
         );
 
-        let horizontal_pipeline_id = specialized_render_pipelines.tfm.specialize(
-            &pipeline_cache,
-            &dog_pipelines.tfm,
-            TFMPipelineKeys::Horizontal,
-        );
+        // the passes of disabled features are neither specialized nor queued
+        let tfm_pipeline_ids = passes.contains(DoGPass::FlowField).then(|| {
+            let mut specialize = |pass| {
+                specialized_render_pipelines.tfm.specialize(
+                    &pipeline_cache,
+                    &dog_pipelines.tfm,
+                    TFMPipelineKeys {
+                        pass,
+                        texture_format: TENSOR_TEXTURE_FORMAT,
+                    },
+                )
+            };
+            TFMPipelineIDs {
+                eigenvector_pipeline_id: specialize(TFMPass::Eigenvector),
+                horizontal_pipeline_id: specialize(TFMPass::Horizontal),
+                vertical_pipeline_id: specialize(TFMPass::Vertical),
+            }
+        });
 
-        let first_fdog = specialized_render_pipelines.fdog.specialize(
-            &pipeline_cache,
-            &dog_pipelines.fdog,
-            FDoGPipelineKeys { first: true },
-        );
+        let fdog_pipeline_ids = passes.contains(DoGPass::FDoG).then(|| FDoGPipelineIDs {
+            first: specialized_render_pipelines.fdog.specialize(
+                &pipeline_cache,
+                &dog_pipelines.fdog,
+                FDoGPipelineKeys {
+                    first: true,
+                    texture_format: first_dog_format,
+                },
+            ),
+            second: specialized_render_pipelines.fdog.specialize(
+                &pipeline_cache,
+                &dog_pipelines.fdog,
+                FDoGPipelineKeys {
+                    first: false,
+                    texture_format: MASK_TEXTURE_FORMAT,
+                },
+            ),
+        });
 
-        let second_fdog = specialized_render_pipelines.fdog.specialize(
-            &pipeline_cache,
-            &dog_pipelines.fdog,
-            FDoGPipelineKeys { first: false },
-        );
+        let dog_pipeline_ids = passes.contains(DoGPass::DoG).then(|| DoGPipelineIDs {
+            first: specialized_render_pipelines.dog.specialize(
+                &pipeline_cache,
+                &dog_pipelines.dog,
+                DoGPipelineKeys {
+                    first: true,
+                    texture_format: first_dog_format,
+                },
+            ),
+            second: specialized_render_pipelines.dog.specialize(
+                &pipeline_cache,
+                &dog_pipelines.dog,
+                DoGPipelineKeys {
+                    first: false,
+                    texture_format: MASK_TEXTURE_FORMAT,
+                },
+            ),
+        });
 
-        let first_dog = specialized_render_pipelines.dog.specialize(
-            &pipeline_cache,
-            &dog_pipelines.dog,
-            DoGPipelineKeys { first: true },
-        );
+        let aa_pipeline_id = passes.contains(DoGPass::AntiAliasing).then(|| {
+            specialized_render_pipelines.aa.specialize(
+                &pipeline_cache,
+                &dog_pipelines.aa,
+                AntiAlliasingPipelineKeys {
+                    texture_format: MASK_TEXTURE_FORMAT,
+                },
+            )
+        });
 
-        let second_dog = specialized_render_pipelines.dog.specialize(
+        let world_space_hatching =
+            uses_world_space_hatching(hatching, depth_prepass, normal_prepass);
+        let blend_pipeline_id = specialized_render_pipelines.blend.specialize(
             &pipeline_cache,
-            &dog_pipelines.dog,
-            DoGPipelineKeys { first: false },
+            &dog_pipelines.blend,
+            BlendPipelineKeys {
+                texture_format: view_target.main_texture_format(),
+                tonal_art_map: hatching
+                    .is_some_and(|hatching| hatching.mode == HatchingMode::TonalArtMap),
+                world_space: world_space_hatching,
+                // only the prepass textures are multisampled
+                multisampled: world_space_hatching && msaa.samples() > 1,
+            },
         );
 
-        let aa_pipeline_id = dog_pipelines.aa.pipeline_id;
-        let blend_pipeline_id = dog_pipelines.blend.pipeline_id;
+        let compute_pipelines = dog_pipelines.compute.as_ref().filter(|_| compute);
+        let compute_pipeline_ids = compute_pipelines.map(|compute_pipelines| {
+            let mut specialize = |enabled: bool, key| {
+                enabled.then(|| {
+                    specialized_render_pipelines.compute.specialize(
+                        &pipeline_cache,
+                        compute_pipelines,
+                        key,
+                    )
+                })
+            };
+            let flow_field = passes.contains(DoGPass::FlowField);
+            let fdog = passes.contains(DoGPass::FDoG);
+            let dog = passes.contains(DoGPass::DoG);
+            GaussianComputePipelineIDs {
+                tfm_horizontal: specialize(flow_field, GaussianComputePipelineKeys::TFMHorizontal),
+                tfm_vertical: specialize(flow_field, GaussianComputePipelineKeys::TFMVertical),
+                fdog_first: specialize(fdog, GaussianComputePipelineKeys::FDoGFirst),
+                dog_first: specialize(dog, GaussianComputePipelineKeys::DoGFirst),
+                dog_second: specialize(dog, GaussianComputePipelineKeys::DoGSecond),
+            }
+        });
 
         commands.entity(entity).insert(GaussianPipelineIDs {
             rgb2lab_pipeline_id,
-            tfm_pipeline_ids: TFMPipelineIDs {
-                eigenvector_pipeline_id,
-                vertical_pipeline_id,
-                horizontal_pipeline_id,
-            },
-            dog_pipeline_ids: DoGPipelineIDs {
-                first: first_dog,
-                second: second_dog,
-            },
-            fdog_pipeline_ids: FDoGPipelineIDs {
-                first: first_fdog,
-                second: second_fdog,
-            },
+            tfm_pipeline_ids,
+            dog_pipeline_ids,
+            fdog_pipeline_ids,
             aa_pipeline_id,
             blend_pipeline_id,
+            world_space_hatching,
+            compute_pipeline_ids,
         });
     }
 }
@@ -1,12 +1,21 @@
 use crate::{
+    hatching::{add_hatching_prepasses, add_tonal_art_map_mips, DoGHatching, TonalArtMapTones},
     node::{prepare_dog_bind_groups, DoGNode},
     pipeline::{prepare_gaussian_pipelines, DoGPipelines, DoGSpecializedRenderPipelines},
-    settings::{DoGSettings, PassesSettings},
+    preset::{
+        apply_dog_presets, resolve_dog_preset_names, DoGPreset, DoGPresetHandle, DoGPresetLoader,
+        DoGPresetName, DoGPresets,
+    },
+    settings::{DoGSettings, DoGSettingsUniform, PassesSettings},
     textures::prepare_dog_textures,
+    transition::{animate_dog_transitions, DoGTransition},
 };
 use bevy::{
-    asset::{load_internal_asset, weak_handle, RenderAssetUsages},
-    core_pipeline::core_3d::graph::{Core3d, Node3d},
+    asset::{load_internal_asset, weak_handle, AssetEvents, RenderAssetUsages},
+    core_pipeline::{
+        core_2d::graph::{Core2d, Node2d},
+        core_3d::graph::{Core3d, Node3d},
+    },
     prelude::*,
     render::{
         extract_component::{ExtractComponentPlugin, UniformComponentPlugin},
@@ -16,8 +25,24 @@ use bevy::{
     },
 };
 
+/// The pngs of the bundled crosshatch textures, the first is also used by the cpu passes.
+pub(crate) const CROSSHATCH_TEXTURE_BYTES: [&[u8]; 4] = [
+    include_bytes!("../assets/textures/bw_crosshatch1.png"),
+    include_bytes!("../assets/textures/bw_crosshatch2.png"),
+    include_bytes!("../assets/textures/bw_crosshatch3.png"),
+    include_bytes!("../assets/textures/bw_crosshatch4.png"),
+];
+
+/// The texture of every hatch layer of cameras without a [`DoGHatching`].
 pub const CROSSHATCH_TEXTURE_HANDLE: Handle<Image> =
     weak_handle!("3bc8be12-aa9d-481f-bce3-56ad52cdfea4");
+/// The bundled crosshatch textures, which [`DoGHatching::default`] uses for the four layers.
+pub const CROSSHATCH_TEXTURE_HANDLES: [Handle<Image>; 4] = [
+    CROSSHATCH_TEXTURE_HANDLE,
+    weak_handle!("0f6d3a2e-5c47-4b9e-8a1d-7e2c9b4f6a13"),
+    weak_handle!("c5a81e4d-92f3-4d6b-b07e-3f1a8c6d2e95"),
+    weak_handle!("6e2b9f70-1d8a-4c35-a4e6-d9b3057c8f21"),
+];
 pub const RGB2LAB_SHADER_HANDLE: Handle<Shader> =
     weak_handle!("ce20ba6c-9bd1-4a62-bfe8-ba51750dbdc3");
 pub const TFM_SHADER_HANDLE: Handle<Shader> = weak_handle!("34197afe-e54f-4d38-9fc6-7c467524cc59");
@@ -26,46 +51,93 @@ pub const AA_SHADER_HANDLE: Handle<Shader> = weak_handle!("9d4d296b-cb2d-4e25-81
 pub const DOG_SHADER_HANDLE: Handle<Shader> = weak_handle!("bedbea43-8967-4cdd-95b6-d3a4d630c436");
 pub const BLEND_SHADER_HANDLE: Handle<Shader> =
     weak_handle!("b86e54d8-858a-41e5-84d5-62a34f455a77");
+pub const GAUSSIAN_COMPUTE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("5d0f7c1e-2a4b-4f7e-9c36-81b0e5a3d2f4");
+pub const SETTINGS_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("a3e1f6d2-7b84-4c09-9e5a-2f6c8d1b4e70");
+pub const THRESHOLD_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("7c2e9a41-3f5d-4b86-a0e7-1d94c6b8f253");
 
 /// It is generally encouraged to set up post processing effects as a plugin
-pub struct DoGPlugin;
+#[derive(Default)]
+pub struct DoGPlugin {
+    /// Where the DoG node runs in the post processing chain of 2d and 3d cameras.
+    pub placement: DoGPlacement,
+}
+
+/// The position of the DoG node in the render graph.
+///
+/// This decides on which data the edges are computed and which other effects see the lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DoGPlacement {
+    /// Runs on the linear (hdr) radiance, after TAA, bloom, depth of field and the other
+    /// built-in post processing, but before tonemapping. The lines will be tonemapped, and on
+    /// hdr cameras the colors above 1 are kept for it.
+    BeforeTonemapping,
+    /// Runs on the tonemapped display values, before FXAA, SMAA and contrast adaptive
+    /// sharpening, so the lines get anti aliased by those.
+    #[default]
+    AfterTonemapping,
+    /// Runs after all post processing including FXAA and SMAA, right before upscaling.
+    /// The lines stay exactly as the DoG passes produce them.
+    AfterAntiAliasing,
+}
 
 impl Plugin for DoGPlugin {
     fn build(&self, app: &mut App) {
         // so I seem to register it too?
         app.register_type::<DoGSettings>().add_plugins((
             ExtractComponentPlugin::<DoGSettings>::default(),
-            UniformComponentPlugin::<DoGSettings>::default(),
-        ));
-        app.register_type::<PassesSettings>().add_plugins((
-            ExtractComponentPlugin::<PassesSettings>::default(),
-            UniformComponentPlugin::<PassesSettings>::default(),
+            UniformComponentPlugin::<DoGSettingsUniform>::default(),
         ));
+        app.register_type::<PassesSettings>()
+            .add_plugins(ExtractComponentPlugin::<PassesSettings>::default());
+        app.init_asset::<DoGPreset>()
+            .register_asset_reflect::<DoGPreset>()
+            .register_type::<DoGPresetHandle>()
+            .register_type::<DoGPresetName>()
+            .init_asset_loader::<DoGPresetLoader>()
+            .init_resource::<DoGPresets>()
+            // after the asset events of this frame, so hot reloads show up right away
+            .add_systems(
+                PostUpdate,
+                (resolve_dog_preset_names, apply_dog_presets)
+                    .chain()
+                    .after(AssetEvents),
+            );
 
-        let diffuse_bytes = include_bytes!("../assets/textures/bw_crosshatch1.png");
+        app.register_type::<DoGTransition>()
+            .add_systems(Update, animate_dog_transitions);
 
-        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
-        let diffuse_rgba = diffuse_image.to_rgba8();
+        app.register_type::<DoGHatching>()
+            .add_plugins((
+                ExtractComponentPlugin::<DoGHatching>::default(),
+                ExtractComponentPlugin::<TonalArtMapTones>::default(),
+            ))
+            .add_systems(PostUpdate, add_hatching_prepasses)
+            .add_systems(PostUpdate, add_tonal_art_map_mips.after(AssetEvents));
 
-        use image::GenericImageView;
-        let dimensions = diffuse_image.dimensions();
-        // this is for the texture
-        let size = Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
-        let crosshatch_image = Image::new_fill(
-            size,
-            TextureDimension::D2,
-            bytemuck::cast_slice(&diffuse_rgba),
-            TextureFormat::Rgba8UnormSrgb,
-            RenderAssetUsages::RENDER_WORLD,
+        let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+        for (handle, bytes) in CROSSHATCH_TEXTURE_HANDLES
+            .iter()
+            .zip(CROSSHATCH_TEXTURE_BYTES)
+        {
+            images.insert(handle.id(), crosshatch_image(bytes));
+        }
+
+        load_internal_asset!(
+            app,
+            SETTINGS_SHADER_HANDLE,
+            "../assets/shaders/settings.wgsl",
+            Shader::from_wgsl
         );
 
-        app.world_mut()
-            .resource_mut::<Assets<Image>>()
-            .insert(CROSSHATCH_TEXTURE_HANDLE.id(), crosshatch_image);
+        load_internal_asset!(
+            app,
+            THRESHOLD_SHADER_HANDLE,
+            "../assets/shaders/threshold.wgsl",
+            Shader::from_wgsl
+        );
 
         load_internal_asset!(
             app,
@@ -109,6 +181,13 @@ impl Plugin for DoGPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            GAUSSIAN_COMPUTE_SHADER_HANDLE,
+            "../assets/shaders/gaussian_compute.wgsl",
+            Shader::from_wgsl
+        );
+
         // We need to get the render app from the main app
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -129,16 +208,70 @@ impl Plugin for DoGPlugin {
                 Core3d, // It also needs the label of the node
                 DoGLabel,
             )
-            .add_render_graph_edges(
-                Core3d,
-                // Specify the node ordering.
-                // This will automatically create all required node dogs to enforce the given ordering.
-                (
-                    Node3d::Tonemapping,
-                    DoGLabel,
-                    Node3d::EndMainPassPostProcessing,
-                ),
-            );
+            // the same node also runs for 2d cameras, it only relies on the view target
+            .add_render_graph_node::<ViewNodeRunner<DoGNode>>(Core2d, DoGLabel);
+
+        // Specify the node ordering.
+        // This will automatically create all required node dogs to enforce the given ordering.
+        match self.placement {
+            DoGPlacement::BeforeTonemapping => {
+                render_app
+                    .add_render_graph_edges(
+                        Core3d,
+                        (Node3d::PostProcessing, DoGLabel, Node3d::Tonemapping),
+                    )
+                    .add_render_graph_edges(
+                        Core2d,
+                        (Node2d::PostProcessing, DoGLabel, Node2d::Tonemapping),
+                    );
+            }
+            DoGPlacement::AfterTonemapping => {
+                // the anti aliasing and sharpening nodes also sit between tonemapping and the end
+                // of the post processing, so they need their own edges to run after the DoG
+                render_app
+                    .add_render_graph_edges(
+                        Core3d,
+                        (
+                            Node3d::Tonemapping,
+                            DoGLabel,
+                            Node3d::EndMainPassPostProcessing,
+                        ),
+                    )
+                    .add_render_graph_edges(Core3d, (DoGLabel, Node3d::Fxaa))
+                    .add_render_graph_edges(Core3d, (DoGLabel, Node3d::Smaa))
+                    .add_render_graph_edges(Core3d, (DoGLabel, Node3d::ContrastAdaptiveSharpening))
+                    .add_render_graph_edges(
+                        Core2d,
+                        (
+                            Node2d::Tonemapping,
+                            DoGLabel,
+                            Node2d::EndMainPassPostProcessing,
+                        ),
+                    )
+                    .add_render_graph_edges(Core2d, (DoGLabel, Node2d::Fxaa))
+                    .add_render_graph_edges(Core2d, (DoGLabel, Node2d::Smaa))
+                    .add_render_graph_edges(Core2d, (DoGLabel, Node2d::ContrastAdaptiveSharpening));
+            }
+            DoGPlacement::AfterAntiAliasing => {
+                render_app
+                    .add_render_graph_edges(
+                        Core3d,
+                        (
+                            Node3d::EndMainPassPostProcessing,
+                            DoGLabel,
+                            Node3d::Upscaling,
+                        ),
+                    )
+                    .add_render_graph_edges(
+                        Core2d,
+                        (
+                            Node2d::EndMainPassPostProcessing,
+                            DoGLabel,
+                            Node2d::Upscaling,
+                        ),
+                    );
+            }
+        }
     }
 
     fn finish(&self, app: &mut App) {
@@ -154,3 +287,21 @@ impl Plugin for DoGPlugin {
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
 pub struct DoGLabel;
+
+/// Decodes a bundled crosshatch texture, with the mip chain of a tonal art map tone.
+pub(crate) fn crosshatch_image(bytes: &[u8]) -> Image {
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let mut crosshatch_image = Image::new_uninit(
+        Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        TextureFormat::Rgba8UnormSrgb,
+        // the main world keeps the texels for the stroke mips of tonal art maps
+        RenderAssetUsages::default(),
+    );
+    crosshatch_image.data = Some(image.into_raw());
+    crosshatch_image
+}
@@ -0,0 +1,286 @@
+use crate::settings::{DoGSettings, PassesSettings};
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    platform::collections::HashSet,
+    prelude::*,
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        GetTypeRegistration, TypeRegistry, TypeRegistryArc,
+    },
+};
+use std::fmt;
+
+/// Settings of the effect stored in a `.dog.ron` file, so they can be tweaked without
+/// recompiling and, with bevy's `file_watcher` feature, while the game runs.
+///
+/// Fields that are left out keep their default value, so a preset only needs to list what it
+/// changes:
+///
+/// ```ron
+/// (
+///     settings: (
+///         thresholding: Tanh,
+///         tau: 15.0,
+///     ),
+///     passes: (
+///         fdog: true,
+///     ),
+/// )
+/// ```
+#[derive(Asset, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Default)]
+pub struct DoGPreset {
+    pub settings: DoGSettings,
+    pub passes: PassesSettings,
+}
+
+impl DoGPreset {
+    /// Parses a preset in the format of the `.dog.ron` files, `registry` has to contain the
+    /// settings types, which [`DoGPlugin`](crate::plugin::DoGPlugin) registers.
+    pub fn from_ron(source: &str, registry: &TypeRegistry) -> Result<Self, DoGPresetError> {
+        let registration = Self::get_type_registration();
+        let preset = ron::Options::default().from_str_seed(
+            source,
+            TypedReflectDeserializer::new(&registration, registry),
+        )?;
+        Self::from_reflect(preset.as_partial_reflect()).ok_or(DoGPresetError::InvalidPreset)
+    }
+
+    /// Writes the preset in the format of the `.dog.ron` files, with every field.
+    pub fn to_ron(&self, registry: &TypeRegistry) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(
+            &TypedReflectSerializer::new(self, registry),
+            ron::ser::PrettyConfig::default(),
+        )
+    }
+}
+
+/// Applies a [`DoGPreset`] to the camera it is on, by inserting its [`DoGSettings`] and
+/// [`PassesSettings`] whenever the handle changes or the asset is loaded or modified.
+///
+/// In between, the settings on the camera can be changed as usual, e.g. from a debug ui.
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct DoGPresetHandle(pub Handle<DoGPreset>);
+
+/// Switches the camera to the preset of this name in [`DoGPresets`], by inserting its
+/// [`DoGPresetHandle`].
+///
+/// Setting the name again applies the preset again, even if it didn't change.
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct DoGPresetName(pub String);
+
+impl DoGPresetName {
+    pub fn new(name: impl Into<String>) -> Self {
+        DoGPresetName(name.into())
+    }
+}
+
+/// The presets by name, e.g. to list them in a menu or to switch cameras between them with
+/// [`DoGPresetName`].
+///
+/// It starts out with [`DoGSettings::PRESETS`], plugins and apps can add their own, like loaded
+/// `.dog.ron` files:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_dog::preset::DoGPresets;
+/// fn register_presets(mut presets: ResMut<DoGPresets>, asset_server: Res<AssetServer>) {
+///     presets.insert("sketch", asset_server.load("presets/sketch.dog.ron"));
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct DoGPresets {
+    presets: Vec<(String, Handle<DoGPreset>)>,
+}
+
+impl DoGPresets {
+    /// Registers `preset` under `name`. A preset that already has this name is replaced, but
+    /// keeps its place in the order, and is returned.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        preset: Handle<DoGPreset>,
+    ) -> Option<Handle<DoGPreset>> {
+        let name = name.into();
+        match self.presets.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, handle)) => Some(std::mem::replace(handle, preset)),
+            None => {
+                self.presets.push((name, preset));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Handle<DoGPreset>> {
+        let index = self.presets.iter().position(|(other, _)| other == name)?;
+        Some(self.presets.remove(index).1)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Handle<DoGPreset>> {
+        self.presets
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, handle)| handle)
+    }
+
+    /// The presets in the order they were registered, the built-in ones first.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Handle<DoGPreset>)> {
+        self.presets
+            .iter()
+            .map(|(name, handle)| (name.as_str(), handle))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(name, _)| name)
+    }
+}
+
+impl FromWorld for DoGPresets {
+    fn from_world(world: &mut World) -> Self {
+        let mut assets = world.resource_mut::<Assets<DoGPreset>>();
+        let presets = DoGSettings::PRESETS
+            .iter()
+            .map(|(name, settings)| {
+                let preset = DoGPreset {
+                    settings: *settings,
+                    ..default()
+                };
+                (name.to_string(), assets.add(preset))
+            })
+            .collect();
+        DoGPresets { presets }
+    }
+}
+
+#[derive(Debug)]
+pub enum DoGPresetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// The file parsed, but doesn't describe a [`DoGPreset`].
+    InvalidPreset,
+}
+
+impl fmt::Display for DoGPresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoGPresetError::Io(error) => write!(f, "could not read the preset: {error}"),
+            DoGPresetError::Ron(error) => write!(f, "could not parse the preset: {error}"),
+            DoGPresetError::InvalidPreset => write!(f, "the file is not a DoGPreset"),
+        }
+    }
+}
+
+impl std::error::Error for DoGPresetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DoGPresetError::Io(error) => Some(error),
+            DoGPresetError::Ron(error) => Some(error),
+            DoGPresetError::InvalidPreset => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DoGPresetError {
+    fn from(error: std::io::Error) -> Self {
+        DoGPresetError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for DoGPresetError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        DoGPresetError::Ron(error)
+    }
+}
+
+/// Loads `.dog.ron` files as [`DoGPreset`]s.
+pub struct DoGPresetLoader {
+    type_registry: TypeRegistryArc,
+}
+
+impl FromWorld for DoGPresetLoader {
+    fn from_world(world: &mut World) -> Self {
+        DoGPresetLoader {
+            type_registry: world.resource::<AppTypeRegistry>().0.clone(),
+        }
+    }
+}
+
+impl AssetLoader for DoGPresetLoader {
+    type Asset = DoGPreset;
+    type Settings = ();
+    type Error = DoGPresetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<DoGPreset, DoGPresetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = std::str::from_utf8(&bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        DoGPreset::from_ron(source, &self.type_registry.read())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dog.ron"]
+    }
+}
+
+pub(crate) fn resolve_dog_preset_names(
+    mut commands: Commands,
+    presets: Res<DoGPresets>,
+    cameras: Query<(Entity, Ref<DoGPresetName>, Option<&DoGPresetHandle>)>,
+) {
+    for (entity, name, current) in &cameras {
+        if !name.is_changed() && !presets.is_changed() {
+            continue;
+        }
+        let Some(handle) = presets.get(&name.0) else {
+            if name.is_changed() {
+                warn!("there is no DoG preset named `{}`", name.0);
+            }
+            continue;
+        };
+        // a change of the registry only matters if it replaced the preset of this camera
+        if !name.is_changed() && current.is_some_and(|current| current.0 == *handle) {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .insert(DoGPresetHandle(handle.clone()));
+    }
+}
+
+pub(crate) fn apply_dog_presets(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<DoGPreset>>,
+    presets: Res<Assets<DoGPreset>>,
+    cameras: Query<(Entity, Ref<DoGPresetHandle>)>,
+) {
+    let changed: HashSet<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, handle) in &cameras {
+        if !handle.is_changed() && !changed.contains(&handle.0.id()) {
+            continue;
+        }
+        // the preset is applied once it has loaded
+        let Some(preset) = presets.get(&handle.0) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .insert((preset.settings, preset.passes));
+    }
+}
@@ -1,32 +1,39 @@
 use bevy::{
+    ecs::query::QueryItem,
     math::{Vec2, Vec3, Vec4},
     prelude::*,
     reflect::Reflect,
     render::{extract_component::ExtractComponent, render_resource::ShaderType},
 };
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+/// The shaders compare `DoGSettings.thresholding` against these values.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(i32)]
 pub enum Thresholding {
-    NoThreshold,
-    Tanh,
-    Quantization,
-    SmoothQuantization,
+    #[default]
+    NoThreshold = 0,
+    Tanh = 1,
+    Quantization = 2,
+    SmoothQuantization = 3,
 }
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+/// The shaders compare `DoGSettings.blend_mode` against these values.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(i32)]
 pub enum BlendMode {
-    NoBlend,
-    Interpolate,
-    TwoPointInterpolate,
+    #[default]
+    NoBlend = 0,
+    Interpolate = 1,
+    TwoPointInterpolate = 2,
 }
 
-#[derive(Reflect, Debug, Component, Clone, Copy, ExtractComponent, ShaderType)]
-#[reflect(Component)]
+#[derive(Reflect, Debug, Component, Clone, Copy)]
+#[reflect(Component, Default)]
 pub struct DoGSettings {
-    pub thresholding: i32,
-    pub blend_mode: i32,
-    pub invert: i32,
-    pub calc_diff_before_convolution: i32,
+    pub thresholding: Thresholding,
+    pub blend_mode: BlendMode,
+    pub invert: bool,
+    pub calc_diff_before_convolution: bool,
     pub sigma_c: f32,
     pub sigma_e: f32,
     pub sigma_m: f32,
@@ -41,20 +48,40 @@ pub struct DoGSettings {
     pub edge_smooth_step_sizes: Vec2,
     pub min_color: Vec3,
     pub max_color: Vec3,
-    pub enable_hatch: i32,
+    pub enable_hatch: bool,
     pub enable_layers: Vec4,
+    /// The density of the hatching of every layer, in texels of its texture per logical pixel.
+    /// At 1 the texture is drawn at its size, at 2 at half of it, on every window size, aspect
+    /// ratio and scale factor.
     pub hatch_resolutions: Vec4,
+    /// The rotation of the hatching of every layer in degrees.
     pub hatch_rotations: Vec4,
+    /// The repeats of the hatching per world unit, with
+    /// [`HatchingSpace::World`](crate::hatching::HatchingSpace::World). It replaces
+    /// `hatch_resolutions` on surfaces, whose size on the screen changes with their distance.
+    pub world_hatch_scale: f32,
+    /// Draws the hatching with procedural strokes instead of the hatching textures. The stroke
+    /// parameters of every layer are measured in texels of a virtual 512 texel texture, so
+    /// `hatch_resolutions` scales them like the bundled textures.
+    pub procedural_hatch: bool,
+    /// The distance between two procedural strokes.
+    pub hatch_spacings: Vec4,
+    /// The width of a procedural stroke.
+    pub hatch_thicknesses: Vec4,
+    /// How far every procedural stroke is randomly shifted, in fractions of the spacing.
+    pub hatch_jitters: Vec4,
+    /// How far the procedural strokes bend along their length, in fractions of the spacing.
+    pub hatch_wobbles: Vec4,
     pub thresholds: Vec4,
 }
 
 impl Default for DoGSettings {
     fn default() -> Self {
         DoGSettings {
-            thresholding: Thresholding::NoThreshold as i32,
-            blend_mode: BlendMode::NoBlend as i32,
-            invert: 0,
-            calc_diff_before_convolution: 0,
+            thresholding: Thresholding::NoThreshold,
+            blend_mode: BlendMode::NoBlend,
+            invert: false,
+            calc_diff_before_convolution: false,
             sigma_c: 2.0,
             sigma_e: 2.0,
             sigma_m: 2.0,
@@ -69,10 +96,16 @@ impl Default for DoGSettings {
             edge_smooth_step_sizes: Vec2::ONE,
             min_color: Vec3::ZERO,
             max_color: Vec3::ONE,
-            enable_hatch: 0,
+            enable_hatch: false,
             enable_layers: Vec4::from_array([1., 0., 0., 0.]),
             hatch_resolutions: Vec4::ONE,
             hatch_rotations: Vec4::from_array([15., 60., 105., 170.]),
+            world_hatch_scale: 1.0,
+            procedural_hatch: false,
+            hatch_spacings: Vec4::splat(20.0),
+            hatch_thicknesses: Vec4::splat(7.0),
+            hatch_jitters: Vec4::splat(0.3),
+            hatch_wobbles: Vec4::splat(0.1),
             thresholds: Vec4::from_array([90.0, 20.0, 30.0, 40.0]),
         }
     }
@@ -80,10 +113,10 @@ impl Default for DoGSettings {
 
 impl DoGSettings {
     pub const DEFAULT: Self = Self {
-        thresholding: Thresholding::NoThreshold as i32,
-        blend_mode: BlendMode::NoBlend as i32,
-        invert: 0,
-        calc_diff_before_convolution: 0,
+        thresholding: Thresholding::NoThreshold,
+        blend_mode: BlendMode::NoBlend,
+        invert: false,
+        calc_diff_before_convolution: false,
         sigma_c: 2.0,
         sigma_e: 2.0,
         sigma_m: 2.0,
@@ -98,17 +131,23 @@ impl DoGSettings {
         edge_smooth_step_sizes: Vec2::ONE,
         min_color: Vec3::ZERO,
         max_color: Vec3::ONE,
-        enable_hatch: 0,
+        enable_hatch: false,
         enable_layers: Vec4::from_array([1., 0., 0., 0.]),
         hatch_resolutions: Vec4::ONE,
         hatch_rotations: Vec4::from_array([15., 60., 105., 170.]),
+        world_hatch_scale: 1.0,
+        procedural_hatch: false,
+        hatch_spacings: Vec4::splat(20.0),
+        hatch_thicknesses: Vec4::splat(7.0),
+        hatch_jitters: Vec4::splat(0.3),
+        hatch_wobbles: Vec4::splat(0.1),
         thresholds: Vec4::from_array([90.0, 20.0, 30.0, 40.0]),
     };
     pub const OUTLINE_DITHER: Self = Self {
         k: 0.5,
         tau: 32.,
         phi: 0.8,
-        thresholding: Thresholding::Tanh as i32,
+        thresholding: Thresholding::Tanh,
         thresholds: Vec4::from_array([12., 6., 3., 0.5]),
         min_color: Vec3::from_array([0.5, 0.4, 0.4]),
         max_color: Vec3::from_array([0.8, 1.0, 0.9]),
@@ -119,27 +158,249 @@ impl DoGSettings {
         blend_strength: 0.9,
         max_color: Vec3::from_array([0.8, 0.85, 0.81]),
         phi: 2.0,
-        thresholding: Thresholding::Tanh as i32,
+        thresholding: Thresholding::Tanh,
         enable_layers: Vec4::from_array([1., 1., 1., 1.]),
         thresholds: Vec4::from_array([0.2, 1.3, 0.7, 0.5]),
-        enable_hatch: 1,
-        hatch_resolutions: Vec4::splat(6.2),
+        enable_hatch: true,
+        hatch_resolutions: Vec4::splat(2.5),
         ..Self::DEFAULT
     };
     pub const OUTLINE: Self = Self {
         tau: 15.0,
         ..Self::DEFAULT
     };
+    /// Interpolates the continuous fields towards `other` by `t`. The discrete fields, the modes,
+    /// the flags and `enable_layers`, can't be blended and are kept from `self`.
+    pub fn lerp_continuous(&self, other: &Self, t: f32) -> Self {
+        Self {
+            sigma_c: self.sigma_c.lerp(other.sigma_c, t),
+            sigma_e: self.sigma_e.lerp(other.sigma_e, t),
+            sigma_m: self.sigma_m.lerp(other.sigma_m, t),
+            sigma_a: self.sigma_a.lerp(other.sigma_a, t),
+            quantizer_step: self.quantizer_step.lerp(other.quantizer_step, t),
+            k: self.k.lerp(other.k, t),
+            tau: self.tau.lerp(other.tau, t),
+            phi: self.phi.lerp(other.phi, t),
+            blend_strength: self.blend_strength.lerp(other.blend_strength, t),
+            dog_strength: self.dog_strength.lerp(other.dog_strength, t),
+            line_conv_step_sizes: self
+                .line_conv_step_sizes
+                .lerp(other.line_conv_step_sizes, t),
+            edge_smooth_step_sizes: self
+                .edge_smooth_step_sizes
+                .lerp(other.edge_smooth_step_sizes, t),
+            min_color: self.min_color.lerp(other.min_color, t),
+            max_color: self.max_color.lerp(other.max_color, t),
+            hatch_resolutions: self.hatch_resolutions.lerp(other.hatch_resolutions, t),
+            hatch_rotations: self.hatch_rotations.lerp(other.hatch_rotations, t),
+            world_hatch_scale: self.world_hatch_scale.lerp(other.world_hatch_scale, t),
+            hatch_spacings: self.hatch_spacings.lerp(other.hatch_spacings, t),
+            hatch_thicknesses: self.hatch_thicknesses.lerp(other.hatch_thicknesses, t),
+            hatch_jitters: self.hatch_jitters.lerp(other.hatch_jitters, t),
+            hatch_wobbles: self.hatch_wobbles.lerp(other.hatch_wobbles, t),
+            thresholds: self.thresholds.lerp(other.thresholds, t),
+            ..*self
+        }
+    }
+
+    /// The presets above with their names, which [`DoGPresets`](crate::preset::DoGPresets)
+    /// starts out with.
+    pub const PRESETS: [(&'static str, Self); 4] = [
+        ("default", Self::DEFAULT),
+        ("outline", Self::OUTLINE),
+        ("outline_dither", Self::OUTLINE_DITHER),
+        ("crosshatch", Self::CROSSHATCH),
+    ];
+}
+
+impl ExtractComponent for DoGSettings {
+    type QueryData = (&'static Self, Option<&'static Camera>);
+    type QueryFilter = ();
+    type Out = DoGSettingsUniform;
+
+    fn extract_component((settings, camera): QueryItem<'_, Self::QueryData>) -> Option<Self::Out> {
+        let scale_factor = camera
+            .and_then(Camera::target_scaling_factor)
+            .unwrap_or(1.0);
+        Some(DoGSettingsUniform {
+            scale_factor,
+            ..settings.into()
+        })
+    }
+}
+
+/// The [`DoGSettings`] as the shaders see them, with the enums and bools packed into `i32`s.
+///
+/// This is what gets extracted to the render world and uploaded as a uniform.
+#[derive(Debug, Component, Clone, Copy, ShaderType)]
+pub struct DoGSettingsUniform {
+    pub(crate) thresholding: i32,
+    pub(crate) blend_mode: i32,
+    pub(crate) invert: i32,
+    pub(crate) calc_diff_before_convolution: i32,
+    pub(crate) sigma_c: f32,
+    pub(crate) sigma_e: f32,
+    pub(crate) sigma_m: f32,
+    pub(crate) sigma_a: f32,
+    pub(crate) quantizer_step: f32,
+    pub(crate) k: f32,
+    pub(crate) tau: f32,
+    pub(crate) phi: f32,
+    pub(crate) blend_strength: f32,
+    pub(crate) dog_strength: f32,
+    pub(crate) line_conv_step_sizes: Vec2,
+    pub(crate) edge_smooth_step_sizes: Vec2,
+    pub(crate) min_color: Vec3,
+    pub(crate) max_color: Vec3,
+    pub(crate) enable_hatch: i32,
+    pub(crate) enable_layers: Vec4,
+    pub(crate) hatch_resolutions: Vec4,
+    pub(crate) hatch_rotations: Vec4,
+    pub(crate) world_hatch_scale: f32,
+    pub(crate) procedural_hatch: i32,
+    pub(crate) hatch_spacings: Vec4,
+    pub(crate) hatch_thicknesses: Vec4,
+    pub(crate) hatch_jitters: Vec4,
+    pub(crate) hatch_wobbles: Vec4,
+    pub(crate) thresholds: Vec4,
+    /// The scale factor of the render target, which the hatching is measured in.
+    pub(crate) scale_factor: f32,
 }
 
-#[derive(Reflect, Debug, Component, Clone, Copy, ExtractComponent, ShaderType)]
-#[reflect(Component)]
+impl From<&DoGSettings> for DoGSettingsUniform {
+    fn from(settings: &DoGSettings) -> Self {
+        DoGSettingsUniform {
+            thresholding: settings.thresholding as i32,
+            blend_mode: settings.blend_mode as i32,
+            invert: settings.invert as i32,
+            calc_diff_before_convolution: settings.calc_diff_before_convolution as i32,
+            sigma_c: settings.sigma_c,
+            sigma_e: settings.sigma_e,
+            sigma_m: settings.sigma_m,
+            sigma_a: settings.sigma_a,
+            quantizer_step: settings.quantizer_step,
+            k: settings.k,
+            tau: settings.tau,
+            phi: settings.phi,
+            blend_strength: settings.blend_strength,
+            dog_strength: settings.dog_strength,
+            line_conv_step_sizes: settings.line_conv_step_sizes,
+            edge_smooth_step_sizes: settings.edge_smooth_step_sizes,
+            min_color: settings.min_color,
+            max_color: settings.max_color,
+            enable_hatch: settings.enable_hatch as i32,
+            enable_layers: settings.enable_layers,
+            hatch_resolutions: settings.hatch_resolutions,
+            hatch_rotations: settings.hatch_rotations,
+            world_hatch_scale: settings.world_hatch_scale,
+            procedural_hatch: settings.procedural_hatch as i32,
+            hatch_spacings: settings.hatch_spacings,
+            hatch_thicknesses: settings.hatch_thicknesses,
+            hatch_jitters: settings.hatch_jitters,
+            hatch_wobbles: settings.hatch_wobbles,
+            thresholds: settings.thresholds,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+/// Where the separable gaussian blurs run.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GaussianBackend {
+    #[default]
+    Fragment,
+    /// Compute shaders with workgroup shared memory. Kernels are clamped to a radius of 32 pixels
+    /// (16 for the FDoG gradient blur) and devices without compute shader support always use the
+    /// fragment passes.
+    Compute,
+}
+
+#[derive(Reflect, Debug, Component, Clone, Copy, ExtractComponent)]
+#[reflect(Component, Default)]
 pub struct PassesSettings {
-    pub aa: i32,
-    pub tfm: i32,
+    /// Smooths the lines along the flow field.
+    pub anti_aliasing: bool,
+    /// Uses the flow-based DoG, which blurs along the flow field, instead of the plain DoG.
+    pub fdog: bool,
+    pub backend: GaussianBackend,
+    /// Runs everything but the final blend at this fraction of the camera resolution, e.g. 0.5 or
+    /// 0.25. The result is upsampled with a luminance-guided filter, so lines stay on the edges of
+    /// the full resolution image.
+    pub resolution_scale: f32,
 }
+
 impl Default for PassesSettings {
     fn default() -> Self {
-        PassesSettings { aa: 0, tfm: 0 }
+        PassesSettings {
+            anti_aliasing: false,
+            fdog: false,
+            backend: GaussianBackend::Fragment,
+            resolution_scale: 1.0,
+        }
+    }
+}
+
+impl PassesSettings {
+    /// The passes that run for these settings, including every pass they depend on.
+    pub fn passes(&self) -> DoGPasses {
+        let mut passes = DoGPasses::default();
+        passes.insert(DoGPass::Blend);
+        passes.insert(if self.fdog {
+            DoGPass::FDoG
+        } else {
+            DoGPass::DoG
+        });
+        if self.anti_aliasing {
+            passes.insert(DoGPass::AntiAliasing);
+        }
+        passes
+    }
+}
+
+/// The passes of the effect, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoGPass {
+    /// Converts the view target to lab color.
+    Lab,
+    /// The structure tensor and the flow field along the edges, takes three passes.
+    FlowField,
+    /// The flow-based DoG.
+    FDoG,
+    /// The plain DoG, runs if the FDoG doesn't.
+    DoG,
+    /// Smooths the lines along the flow field.
+    AntiAliasing,
+    /// Blends the lines with the view target.
+    Blend,
+}
+
+impl DoGPass {
+    /// The passes whose output this pass reads. The anti aliasing and blend pass additionally
+    /// read the lines of whichever DoG runs.
+    pub fn dependencies(self) -> &'static [DoGPass] {
+        match self {
+            DoGPass::Lab | DoGPass::Blend => &[],
+            DoGPass::FlowField | DoGPass::DoG => &[DoGPass::Lab],
+            DoGPass::FDoG => &[DoGPass::Lab, DoGPass::FlowField],
+            DoGPass::AntiAliasing => &[DoGPass::FlowField],
+        }
+    }
+}
+
+/// A set of passes, which always contains the dependencies of its passes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DoGPasses(u8);
+
+impl DoGPasses {
+    /// Adds the pass and everything it depends on.
+    pub fn insert(&mut self, pass: DoGPass) {
+        self.0 |= 1 << pass as u8;
+        for dependency in pass.dependencies() {
+            self.insert(*dependency);
+        }
+    }
+
+    pub fn contains(self, pass: DoGPass) -> bool {
+        self.0 & (1 << pass as u8) != 0
     }
 }
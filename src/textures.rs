@@ -1,6 +1,8 @@
-use crate::settings::DoGSettings;
+use crate::{
+    pipeline::{uses_compute_backend, DoGPipelines},
+    settings::{DoGPass, DoGPasses, DoGSettingsUniform, PassesSettings},
+};
 use bevy::{
-    image::BevyDefault,
     prelude::*,
     render::{
         camera::ExtractedCamera,
@@ -13,35 +15,83 @@ use bevy::{
     },
 };
 
+/// The lab color of the input, 8 bits would band the lightness gradients.
+pub const LAB_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// The structure tensor, its blurred versions and the flow field, which have negative components.
+pub const TENSOR_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// The two gaussians of the DoG.
+pub const GAUSSIAN_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+/// The thresholded lines, one layer per channel, which are always in [0, 1].
+pub const MASK_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+/// The format of the storage textures written by the compute passes, apart from the mask.
+/// `Rg16Float` can't be used as a storage texture.
+pub const COMPUTE_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// The format of the first DoG texture, which holds the two gaussians and for the FDoG also their
+/// difference.
+pub fn first_dog_texture_format(passes: DoGPasses, compute: bool) -> TextureFormat {
+    if compute || passes.contains(DoGPass::FDoG) {
+        COMPUTE_TEXTURE_FORMAT
+    } else {
+        GAUSSIAN_TEXTURE_FORMAT
+    }
+}
+
 #[derive(Component)]
 pub struct DoGTextures {
     pub lab_texture: CachedTexture,
+    /// Only allocated if a pass depends on `DoGPass::FlowField`
+    pub tfm_textures: Option<TFMTextures>,
+    pub first_dog_texture: CachedTexture,
+    pub second_dog_texture: CachedTexture,
+    /// Only allocated if the anti aliasing pass runs
+    pub aa_texture: Option<CachedTexture>,
+}
+
+pub struct TFMTextures {
     pub eigen_texture: CachedTexture,
     pub horizontal_texture: CachedTexture,
     pub vertical_texture: CachedTexture,
-    pub first_dog_texture: CachedTexture,
-    pub second_dog_texture: CachedTexture,
-    pub aa_texture: CachedTexture,
 }
 
+/// The views of the cameras the effect runs for.
+pub(crate) type DoGViews = (With<ExtractedView>, With<DoGSettingsUniform>);
+
 pub fn prepare_dog_textures(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
     mut texture_cache: ResMut<TextureCache>,
-    view_targets: Query<(Entity, &ExtractedCamera), (With<ExtractedView>, With<DoGSettings>)>,
+    dog_pipelines: Res<DoGPipelines>,
+    view_targets: Query<(Entity, &ExtractedCamera, &PassesSettings), DoGViews>,
 ) {
-    for (entity, camera) in &view_targets {
+    for (entity, camera, passes_settings) in &view_targets {
         let Some(texture_size) = camera.physical_target_size else {
             continue;
         };
 
+        // the blurred textures are written by the compute passes, if those are used
+        let passes = passes_settings.passes();
+        let compute = uses_compute_backend(passes_settings, &dog_pipelines);
+        let blur_usage = if compute {
+            TextureUsages::TEXTURE_BINDING
+                | TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::STORAGE_BINDING
+        } else {
+            TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT
+        };
+
+        // everything up to the blend runs at the reduced resolution
+        let texture_size = (texture_size.as_vec2()
+            * passes_settings.resolution_scale.clamp(0.0, 1.0))
+        .round()
+        .as_uvec2()
+        .max(UVec2::ONE);
         let texture_size = Extent3d {
             width: texture_size.x,
             height: texture_size.y,
             depth_or_array_layers: 1,
         };
 
-        // Create the two-channel RG texture for phase 1 (edge detection).
         let lab_texture = texture_cache.get(
             &render_device,
             TextureDescriptor {
@@ -50,51 +100,59 @@ pub fn prepare_dog_textures(
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
+                format: LAB_TEXTURE_FORMAT,
                 usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
                 view_formats: &[],
             },
         );
 
-        let eigen_texture = texture_cache.get(
-            &render_device,
-            TextureDescriptor {
-                label: Some("Eigenvector Texture"),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-        );
-        let horizontal_texture = texture_cache.get(
-            &render_device,
-            TextureDescriptor {
-                label: Some("Horizontal Blur Pass Texture"),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-        );
-        let vertical_texture = texture_cache.get(
-            &render_device,
-            TextureDescriptor {
-                label: Some("Vertical Blur Pass Texture"),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-        );
+        let tfm_textures = passes.contains(DoGPass::FlowField).then(|| {
+            let eigen_texture = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("Eigenvector Texture"),
+                    size: texture_size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TENSOR_TEXTURE_FORMAT,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                },
+            );
+            let horizontal_texture = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("Horizontal Blur Pass Texture"),
+                    size: texture_size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TENSOR_TEXTURE_FORMAT,
+                    usage: blur_usage,
+                    view_formats: &[],
+                },
+            );
+            let vertical_texture = texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("Vertical Blur Pass Texture"),
+                    size: texture_size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TENSOR_TEXTURE_FORMAT,
+                    usage: blur_usage,
+                    view_formats: &[],
+                },
+            );
+
+            TFMTextures {
+                eigen_texture,
+                horizontal_texture,
+                vertical_texture,
+            }
+        });
 
         let first_dog_texture = texture_cache.get(
             &render_device,
@@ -104,8 +162,8 @@ pub fn prepare_dog_textures(
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                format: first_dog_texture_format(passes, compute),
+                usage: blur_usage,
                 view_formats: &[],
             },
         );
@@ -118,31 +176,31 @@ pub fn prepare_dog_textures(
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                format: MASK_TEXTURE_FORMAT,
+                usage: blur_usage,
                 view_formats: &[],
             },
         );
 
-        let aa_texture = texture_cache.get(
-            &render_device,
-            TextureDescriptor {
-                label: Some("AA Pass Texture"),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::bevy_default(),
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-        );
+        let aa_texture = passes.contains(DoGPass::AntiAliasing).then(|| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some("AA Pass Texture"),
+                    size: texture_size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: MASK_TEXTURE_FORMAT,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                },
+            )
+        });
 
         commands.entity(entity).insert(DoGTextures {
             lab_texture,
-            eigen_texture,
-            horizontal_texture,
-            vertical_texture,
+            tfm_textures,
             first_dog_texture,
             second_dog_texture,
             aa_texture,
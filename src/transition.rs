@@ -0,0 +1,109 @@
+use crate::settings::DoGSettings;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Animates the [`DoGSettings`] of the camera it is on towards `to` and removes itself once it
+/// has arrived, e.g. to fade into a flashback.
+///
+/// The continuous fields, like the sigmas, `tau`, the colors and the thresholds, follow `ease`.
+/// The discrete ones, like `thresholding` or `enable_hatch`, switch at `switch_at`.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_dog::{settings::DoGSettings, transition::DoGTransition};
+/// # use std::time::Duration;
+/// fn enter_sketch_vision(mut commands: Commands, camera: Single<Entity, With<Camera3d>>) {
+///     commands.entity(*camera).insert(
+///         DoGTransition::new(DoGSettings::CROSSHATCH, Duration::from_secs(2))
+///             .with_ease(EaseFunction::CubicInOut),
+///     );
+/// }
+/// ```
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct DoGTransition {
+    /// The settings at the start, the settings of the camera when the transition starts if they
+    /// aren't given.
+    pub from: Option<DoGSettings>,
+    pub to: DoGSettings,
+    pub duration: Duration,
+    pub elapsed: Duration,
+    pub ease: EaseFunction,
+    /// The fraction of the duration after which the discrete fields are taken from `to`.
+    pub switch_at: f32,
+}
+
+impl DoGTransition {
+    /// A transition from the current settings of the camera to `to`.
+    pub fn new(to: DoGSettings, duration: Duration) -> Self {
+        DoGTransition {
+            from: None,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+            ease: EaseFunction::SmoothStep,
+            switch_at: 0.5,
+        }
+    }
+
+    pub fn with_from(mut self, from: DoGSettings) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    pub fn with_ease(mut self, ease: EaseFunction) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    pub fn with_switch_at(mut self, switch_at: f32) -> Self {
+        self.switch_at = switch_at;
+        self
+    }
+
+    /// How far the transition is, from 0 to 1, before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// The settings at the current progress.
+    pub fn sample(&self, from: &DoGSettings) -> DoGSettings {
+        let progress = self.progress();
+        if progress >= 1.0 {
+            return self.to;
+        }
+        let t = self.ease.sample_clamped(progress);
+        if progress < self.switch_at {
+            from.lerp_continuous(&self.to, t)
+        } else {
+            self.to.lerp_continuous(from, 1.0 - t)
+        }
+    }
+}
+
+pub(crate) fn animate_dog_transitions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cameras: Query<(Entity, &mut DoGTransition, Option<&mut DoGSettings>)>,
+) {
+    for (entity, mut transition, settings) in &mut cameras {
+        // the start is captured once, afterwards the camera holds the animated settings
+        let current = settings.as_deref().copied().unwrap_or(transition.to);
+        let from = *transition.from.get_or_insert(current);
+        transition.elapsed += time.delta();
+
+        let sampled = transition.sample(&from);
+        match settings {
+            Some(mut settings) => *settings = sampled,
+            None => {
+                commands.entity(entity).insert(sampled);
+            }
+        }
+        if transition.progress() >= 1.0 {
+            commands.entity(entity).remove::<DoGTransition>();
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Plays animation clips on [`DoGSettings`] and checks the interpolation of whole settings.
+
+use bevy::{
+    animation::{
+        animatable::{Animatable, BlendInput},
+        animated_field,
+        animation_curves::AnimatedField,
+        AnimationTarget, AnimationTargetId,
+    },
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+use bevy_dog::{
+    animation::DoGSettingsProperty,
+    settings::{DoGSettings, Thresholding},
+};
+use std::time::Duration;
+
+/// Spawns a camera that plays `clip`, the time advances 0.25 seconds per update.
+fn play(clip: impl FnOnce(&mut AnimationClip, AnimationTargetId)) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), AnimationPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            250,
+        )));
+
+    let name = Name::new("camera");
+    let target = AnimationTargetId::from_name(&name);
+    let mut animation = AnimationClip::default();
+    clip(&mut animation, target);
+    let animation = app
+        .world_mut()
+        .resource_mut::<Assets<AnimationClip>>()
+        .add(animation);
+    let (graph, node) = AnimationGraph::from_clip(animation);
+    let graph = app
+        .world_mut()
+        .resource_mut::<Assets<AnimationGraph>>()
+        .add(graph);
+
+    let mut player = AnimationPlayer::default();
+    player.play(node);
+    let camera = app
+        .world_mut()
+        .spawn((
+            name,
+            DoGSettings::OUTLINE,
+            player,
+            AnimationGraphHandle(graph),
+        ))
+        .id();
+    app.world_mut().entity_mut(camera).insert(AnimationTarget {
+        id: target,
+        player: camera,
+    });
+    (app, camera)
+}
+
+fn settings(app: &App, camera: Entity) -> DoGSettings {
+    *app.world().get::<DoGSettings>(camera).unwrap()
+}
+
+#[test]
+fn fields_follow_their_curves() {
+    let (mut app, camera) = play(|clip, target| {
+        clip.add_curve_to_target(
+            target,
+            AnimatableCurve::new(
+                animated_field!(DoGSettings::tau),
+                AnimatableKeyframeCurve::new([(0.0, 1.0), (1.0, 9.0)]).unwrap(),
+            ),
+        );
+        clip.add_curve_to_target(
+            target,
+            AnimatableCurve::new(
+                animated_field!(DoGSettings::max_color),
+                AnimatableKeyframeCurve::new([(0.0, Vec3::ZERO), (1.0, Vec3::ONE)]).unwrap(),
+            ),
+        );
+    });
+
+    let mut taus = Vec::new();
+    for _ in 0..6 {
+        app.update();
+        taus.push(settings(&app, camera).tau);
+    }
+    assert!(taus.contains(&5.0), "{taus:?}");
+    assert_eq!(*taus.last().unwrap(), 9.0);
+    assert_eq!(settings(&app, camera).max_color, Vec3::ONE);
+    // fields without a curve keep their value
+    assert_eq!(settings(&app, camera).phi, DoGSettings::OUTLINE.phi);
+}
+
+#[test]
+fn whole_settings_follow_an_easing_curve() {
+    let (mut app, camera) = play(|clip, target| {
+        clip.add_curve_to_target(
+            target,
+            AnimatableCurve::new(
+                DoGSettingsProperty,
+                EasingCurve::new(
+                    DoGSettings::OUTLINE,
+                    DoGSettings::CROSSHATCH,
+                    EaseFunction::Linear,
+                ),
+            ),
+        );
+    });
+
+    let mut samples = Vec::new();
+    for _ in 0..6 {
+        app.update();
+        samples.push(settings(&app, camera));
+    }
+
+    let middle = samples
+        .iter()
+        .find(|settings| {
+            settings.tau
+                == DoGSettings::OUTLINE
+                    .tau
+                    .lerp(DoGSettings::CROSSHATCH.tau, 0.5)
+        })
+        .expect("no sample half way");
+    // the discrete fields switch when the curve ends
+    assert_eq!(middle.thresholding, Thresholding::NoThreshold);
+    let end = samples.last().unwrap();
+    assert_eq!(end.tau, DoGSettings::CROSSHATCH.tau);
+    assert_eq!(end.thresholding, Thresholding::Tanh);
+    assert!(end.enable_hatch);
+}
+
+#[test]
+fn blending_weights_the_continuous_fields() {
+    let blended = DoGSettings::blend(
+        [
+            BlendInput {
+                weight: 1.0,
+                value: DoGSettings::OUTLINE,
+                additive: false,
+            },
+            BlendInput {
+                weight: 0.75,
+                value: DoGSettings::CROSSHATCH,
+                additive: false,
+            },
+        ]
+        .into_iter(),
+    );
+
+    assert_eq!(
+        blended.tau,
+        DoGSettings::OUTLINE
+            .tau
+            .lerp(DoGSettings::CROSSHATCH.tau, 0.75)
+    );
+    assert_eq!(blended.thresholding, DoGSettings::OUTLINE.thresholding);
+}
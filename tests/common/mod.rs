@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests.
+
+use bevy::prelude::*;
+use bevy_dog::plugin::DoGPlugin;
+
+/// An app with the [`DoGPlugin`] and the assets it needs, but without a renderer.
+pub fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Shader>()
+        .init_asset::<Image>()
+        .add_plugins(DoGPlugin::default());
+    app
+}
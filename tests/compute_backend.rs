@@ -0,0 +1,34 @@
+//! Checks which devices create the layouts and pipelines of the compute blurs, see
+//! [`supports_compute_backend`]. wgpu rejects storage texture layouts on devices without them, so
+//! WebGL2 has to fall back to the fragment passes before any layout is created.
+
+use bevy::render::render_resource::{DownlevelFlags, WgpuLimits};
+use bevy_dog::pipeline::supports_compute_backend;
+
+#[test]
+fn webgl2_uses_the_fragment_passes() {
+    let webgl2 = WgpuLimits::downlevel_webgl2_defaults();
+    assert_eq!(webgl2.max_storage_textures_per_shader_stage, 0);
+    assert!(!supports_compute_backend(DownlevelFlags::empty(), &webgl2));
+    // even if the flags claim compute shaders, there are no storage textures to write to
+    assert!(!supports_compute_backend(DownlevelFlags::all(), &webgl2));
+}
+
+#[test]
+fn compute_needs_compute_shaders_and_storage_textures() {
+    let limits = WgpuLimits::default();
+    assert!(supports_compute_backend(DownlevelFlags::all(), &limits));
+    assert!(!supports_compute_backend(
+        DownlevelFlags::all() - DownlevelFlags::COMPUTE_SHADERS,
+        &limits
+    ));
+
+    let without_storage_textures = WgpuLimits {
+        max_storage_textures_per_shader_stage: 0,
+        ..limits
+    };
+    assert!(!supports_compute_backend(
+        DownlevelFlags::all(),
+        &without_storage_textures
+    ));
+}
@@ -0,0 +1,159 @@
+//! Renders the fixture images in `tests/fixtures/inputs` through every preset with the CPU passes
+//! and compares them against the golden images in `tests/fixtures/golden`.
+//!
+//! After an intended change of a preset or a pass, run the tests with `BLESS_GOLDEN=1` to
+//! overwrite the golden images, and look at the new ones before committing them.
+
+use bevy_dog::{
+    cpu,
+    hatching::HatchingMode,
+    settings::{DoGSettings, PassesSettings},
+};
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+const FIXTURES: &[&str] = &["shapes", "scene"];
+
+/// A channel that differs by more than this from the golden image counts as a mismatch.
+const CHANNEL_TOLERANCE: u8 = 4;
+/// The fraction of pixels that may mismatch, the passes use `powf` and `exp`, whose last bits can
+/// differ between platforms, which can flip a pixel at a threshold.
+const MISMATCH_TOLERANCE: f64 = 0.002;
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Marks every mismatching pixel in red on a faded version of the golden image.
+fn diff_image(actual: &RgbaImage, golden: &RgbaImage) -> (RgbaImage, usize) {
+    let mut mismatches = 0;
+    let diff = RgbaImage::from_fn(golden.width(), golden.height(), |x, y| {
+        let (a, g) = (actual.get_pixel(x, y), golden.get_pixel(x, y));
+        if a.0
+            .iter()
+            .zip(g.0)
+            .any(|(a, g)| a.abs_diff(g) > CHANNEL_TOLERANCE)
+        {
+            mismatches += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba(g.0.map(|c| 192 + c / 4))
+        }
+    });
+    (diff, mismatches)
+}
+
+/// Compares `actual` with the golden image `name`, returns what went wrong.
+fn check_golden(name: &str, actual: &RgbaImage) -> Result<(), String> {
+    let golden_path = fixtures_dir().join("golden").join(format!("{name}.png"));
+    if std::env::var_os("BLESS_GOLDEN").is_some() {
+        actual.save(&golden_path).unwrap();
+        return Ok(());
+    }
+
+    let golden = image::open(&golden_path)
+        .map_err(|error| format!("{name}: can't open {}: {error}", golden_path.display()))?
+        .to_rgba8();
+    if golden.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "{name}: the output is {:?}, but the golden image is {:?}",
+            actual.dimensions(),
+            golden.dimensions()
+        ));
+    }
+
+    let (diff, mismatches) = diff_image(actual, &golden);
+    let pixels = (golden.width() * golden.height()) as usize;
+    if mismatches as f64 <= pixels as f64 * MISMATCH_TOLERANCE {
+        return Ok(());
+    }
+
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let actual_path = output_dir.join(format!("{name}_actual.png"));
+    let diff_path = output_dir.join(format!("{name}_diff.png"));
+    actual.save(&actual_path).unwrap();
+    diff.save(&diff_path).unwrap();
+
+    Err(format!(
+        "{name}: {mismatches} of {pixels} pixels differ from the golden image, see {} and {}",
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+fn check_preset(preset: &str, settings: DoGSettings, passes: PassesSettings) {
+    check_hatching(preset, settings, passes, HatchingMode::Layers);
+}
+
+fn check_hatching(preset: &str, settings: DoGSettings, passes: PassesSettings, mode: HatchingMode) {
+    let failures: Vec<_> = FIXTURES
+        .iter()
+        .filter_map(|fixture| {
+            let input_path = fixtures_dir().join("inputs").join(format!("{fixture}.png"));
+            let input = image::open(&input_path).unwrap().to_rgba8();
+            let output = cpu::process_with_hatching(&input, &settings, &passes, mode);
+            check_golden(&format!("{fixture}_{preset}"), &output).err()
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn outline() {
+    check_preset("outline", DoGSettings::OUTLINE, PassesSettings::default());
+}
+
+#[test]
+fn outline_dither() {
+    check_preset(
+        "outline_dither",
+        DoGSettings::OUTLINE_DITHER,
+        PassesSettings::default(),
+    );
+}
+
+#[test]
+fn crosshatch() {
+    check_preset(
+        "crosshatch",
+        DoGSettings::CROSSHATCH,
+        PassesSettings::default(),
+    );
+}
+
+#[test]
+fn outline_fdog() {
+    check_preset(
+        "outline_fdog",
+        DoGSettings::OUTLINE,
+        PassesSettings {
+            anti_aliasing: true,
+            fdog: true,
+            ..PassesSettings::default()
+        },
+    );
+}
+
+#[test]
+fn crosshatch_procedural() {
+    check_preset(
+        "crosshatch_procedural",
+        DoGSettings {
+            procedural_hatch: true,
+            ..DoGSettings::CROSSHATCH
+        },
+        PassesSettings::default(),
+    );
+}
+
+#[test]
+fn crosshatch_tonal_art_map() {
+    check_hatching(
+        "crosshatch_tonal_art_map",
+        DoGSettings::CROSSHATCH,
+        PassesSettings::default(),
+        HatchingMode::TonalArtMap,
+    );
+}
@@ -0,0 +1,326 @@
+//! Checks the bundled hatch textures and how [`DoGHatching`] picks the image of every layer.
+
+mod common;
+
+use bevy::{
+    asset::{weak_handle, RenderAssetUsages},
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass},
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        sync_world::SyncWorldPlugin,
+    },
+};
+use bevy_dog::{
+    hatching::{DoGHatching, HatchingMode, HatchingSpace, TonalArtMapTones},
+    plugin::{CROSSHATCH_TEXTURE_HANDLE, CROSSHATCH_TEXTURE_HANDLES},
+};
+use common::app;
+
+/// The linear luminance of every texel of `data`.
+fn luminance(data: &[u8]) -> Vec<f32> {
+    data.chunks(4)
+        .map(|texel| LinearRgba::from(Srgba::rgb_u8(texel[0], texel[1], texel[2])).luminance())
+        .collect()
+}
+
+/// The average linear luminance of the first `texels` texels of `data` after `offset`.
+fn tone(data: &[u8], offset: usize, texels: usize) -> f32 {
+    luminance(&data[offset..offset + texels * 4])
+        .iter()
+        .sum::<f32>()
+        / texels as f32
+}
+
+#[test]
+fn bundled_textures_are_added_as_images() {
+    let app = app();
+    let images = app.world().resource::<Assets<Image>>();
+    let sizes: Vec<_> = CROSSHATCH_TEXTURE_HANDLES
+        .iter()
+        .map(|handle| images.get(handle).expect("missing texture").size())
+        .collect();
+    assert_eq!(sizes, [UVec2::splat(512); 4]);
+    // the textures differ, so every layer can look different
+    assert_ne!(
+        images.get(&CROSSHATCH_TEXTURE_HANDLES[0]).unwrap().data,
+        images.get(&CROSSHATCH_TEXTURE_HANDLES[3]).unwrap().data
+    );
+}
+
+#[test]
+fn layers_default_to_the_bundled_textures() {
+    assert_eq!(DoGHatching::default().layers, CROSSHATCH_TEXTURE_HANDLES);
+    assert_eq!(CROSSHATCH_TEXTURE_HANDLES[0], CROSSHATCH_TEXTURE_HANDLE);
+
+    let pencil: Handle<Image> = weak_handle!("8d1f4b2a-6c3e-4f70-9a85-b2e7d0c41f96");
+    let hatching = DoGHatching::default().with_layer(2, pencil.clone());
+    assert_eq!(hatching.layers[1], CROSSHATCH_TEXTURE_HANDLES[1]);
+    assert_eq!(hatching.layers[2], pencil);
+    assert_eq!(
+        DoGHatching::single(pencil.clone()).layers,
+        [pencil.clone(), pencil.clone(), pencil.clone(), pencil]
+    );
+}
+
+/// The images of the tones of `camera`.
+fn tones(app: &App, camera: Entity) -> Vec<Image> {
+    let images = app.world().resource::<Assets<Image>>();
+    app.world()
+        .get::<TonalArtMapTones>(camera)
+        .expect("missing tones")
+        .tones
+        .iter()
+        .map(|tone| images.get(tone).expect("missing tone").clone())
+        .collect()
+}
+
+/// The bundled textures as the tones of a tonal art map.
+fn bundled_tones() -> Vec<Image> {
+    let mut app = app();
+    let camera = app
+        .world_mut()
+        .spawn((
+            Camera3d::default(),
+            DoGHatching::tonal_art_map(CROSSHATCH_TEXTURE_HANDLES),
+        ))
+        .id();
+    app.update();
+    tones(&app, camera)
+}
+
+#[test]
+fn bundled_textures_have_no_mips_for_the_layers() {
+    // the tones of a tonal art map are copies
+    bundled_tones();
+    let app = app();
+    let images = app.world().resource::<Assets<Image>>();
+    for handle in CROSSHATCH_TEXTURE_HANDLES {
+        assert_eq!(
+            images
+                .get(&handle)
+                .unwrap()
+                .texture_descriptor
+                .mip_level_count,
+            1
+        );
+    }
+}
+
+#[test]
+fn bundled_tones_keep_their_tone_in_every_mip() {
+    for image in bundled_tones() {
+        // 512x512 down to 1x1
+        assert_eq!(image.texture_descriptor.mip_level_count, 10);
+        let data = image.data.as_ref().unwrap();
+        let full = tone(data, 0, 512 * 512);
+        let mut offset = 0;
+        for level in 0..10 {
+            let texels = (512usize >> level).pow(2);
+            let level_tone = tone(data, offset, texels);
+            assert!(
+                (level_tone - full).abs() < 0.01,
+                "mip {level} has a tone of {level_tone} instead of {full}"
+            );
+            offset += texels * 4;
+        }
+        assert_eq!(offset, data.len());
+    }
+}
+
+#[test]
+fn tonal_art_maps_are_a_mode_of_the_hatching() {
+    let hatching = DoGHatching::tonal_art_map(CROSSHATCH_TEXTURE_HANDLES);
+    assert_eq!(hatching.mode, HatchingMode::TonalArtMap);
+    assert_eq!(
+        hatching,
+        DoGHatching::default().with_mode(HatchingMode::TonalArtMap)
+    );
+    assert_eq!(DoGHatching::default().mode, HatchingMode::Layers);
+}
+
+#[test]
+fn world_space_hatching_adds_the_prepasses_to_3d_cameras() {
+    let mut app = app();
+    let world = DoGHatching::default().with_space(HatchingSpace::World);
+    let camera_3d = app
+        .world_mut()
+        .spawn((Camera3d::default(), world.clone()))
+        .id();
+    let screen = app
+        .world_mut()
+        .spawn((Camera3d::default(), DoGHatching::default()))
+        .id();
+    let camera_2d = app.world_mut().spawn((Camera2d, world)).id();
+    app.update();
+
+    let prepasses = |app: &App, camera: Entity| {
+        let camera = app.world().entity(camera);
+        (
+            camera.contains::<DepthPrepass>(),
+            camera.contains::<NormalPrepass>(),
+        )
+    };
+    assert_eq!(prepasses(&app, camera_3d), (true, true));
+    assert_eq!(prepasses(&app, screen), (false, false));
+    assert_eq!(prepasses(&app, camera_2d), (false, false));
+
+    // switching an existing camera to world space adds them as well
+    app.world_mut()
+        .get_mut::<DoGHatching>(screen)
+        .unwrap()
+        .space = HatchingSpace::World;
+    app.update();
+    assert_eq!(prepasses(&app, screen), (true, true));
+}
+
+/// The mip levels of a square image, as the linear luminance of every texel.
+fn mip_levels(image: &Image) -> Vec<(u32, Vec<f32>)> {
+    let data = image.data.as_ref().unwrap();
+    let mut offset = 0;
+    (0..image.texture_descriptor.mip_level_count)
+        .map(|level| {
+            let size = (image.width() >> level).max(1);
+            let texels = (size * size) as usize;
+            let luminance = luminance(&data[offset..offset + texels * 4]);
+            offset += texels * 4;
+            (size, luminance)
+        })
+        .collect()
+}
+
+/// The average height of the strokes in the columns of a level, which are dark on light images
+/// and light on dark ones.
+fn stroke_width(size: u32, luminance: &[f32]) -> f32 {
+    let light = luminance.iter().sum::<f32>() / luminance.len() as f32 > 0.5;
+    let mut runs = Vec::new();
+    for x in 0..size {
+        let mut run = 0;
+        for y in 0..size {
+            if (luminance[(y * size + x) as usize] < 0.5) == light {
+                run += 1;
+            } else if run > 0 {
+                runs.push(run);
+                run = 0;
+            }
+        }
+    }
+    runs.iter().sum::<u32>() as f32 / runs.len().max(1) as f32
+}
+
+#[test]
+fn bundled_tones_keep_their_stroke_width_in_the_mips() {
+    for (texture, image) in bundled_tones().iter().enumerate() {
+        let levels = mip_levels(image);
+        let width = stroke_width(levels[0].0, &levels[0].1);
+        // averaged mips would halve the width with every level, down to an eighth at 64x64
+        for (size, luminance) in &levels[1..4] {
+            let level_width = stroke_width(*size, luminance);
+            assert!(
+                level_width > 0.5 * width,
+                "the strokes of texture {texture} are {level_width} texels wide at {size}x{size} \
+                 instead of {width}"
+            );
+        }
+    }
+}
+
+/// A 64x64 image with a black stroke of `width` rows in every 8.
+fn stripes(width: usize) -> Image {
+    let data: Vec<u8> = (0..64 * 64)
+        .flat_map(|texel| {
+            if (texel / 64) % 8 < width {
+                [0, 0, 0, 255]
+            } else {
+                [255; 4]
+            }
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: 64,
+            height: 64,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// Checks the stroke width and tone of the first mip levels of a tone of [`stripes`].
+fn assert_stroke_mips(image: &Image, width: usize) {
+    let levels = mip_levels(image);
+    assert_eq!(levels.len(), 7);
+    let expected_tone = 1.0 - width as f32 / 8.0;
+    for (size, luminance) in &levels[..3] {
+        assert_eq!(
+            stroke_width(*size, luminance),
+            width as f32,
+            "at {size}x{size}"
+        );
+        let tone = luminance.iter().sum::<f32>() / luminance.len() as f32;
+        assert!(
+            (tone - expected_tone).abs() < 0.01,
+            "{tone} at {size}x{size}"
+        );
+    }
+}
+
+#[test]
+fn tonal_art_map_tones_get_stroke_mips_once_loaded() {
+    let mut app = app();
+    // removing the extracted tones needs the entity sync of the render plugin
+    app.add_plugins(SyncWorldPlugin);
+    let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+    let tone = images.add(stripes(2));
+    let layers = images.add(Image::default());
+
+    let layers_camera = app
+        .world_mut()
+        .spawn((Camera3d::default(), DoGHatching::single(layers.clone())))
+        .id();
+    let camera = app
+        .world_mut()
+        .spawn((
+            Camera3d::default(),
+            DoGHatching::tonal_art_map([tone.clone(), tone.clone(), tone.clone(), tone.clone()]),
+        ))
+        .id();
+    app.update();
+
+    // only the tones of tonal art maps get mips, and the images of the layers stay as they are
+    assert!(!app
+        .world()
+        .entity(layers_camera)
+        .contains::<TonalArtMapTones>());
+    let images = app.world().resource::<Assets<Image>>();
+    for image in [&layers, &tone] {
+        assert_eq!(
+            images
+                .get(image)
+                .unwrap()
+                .texture_descriptor
+                .mip_level_count,
+            1
+        );
+    }
+    for image in tones(&app, camera) {
+        assert_stroke_mips(&image, 2);
+    }
+
+    // a reloaded tone gets new mips
+    app.world_mut()
+        .resource_mut::<Assets<Image>>()
+        .insert(&tone, stripes(1));
+    app.update();
+    for image in tones(&app, camera) {
+        assert_stroke_mips(&image, 1);
+    }
+
+    // the tones go away with the tonal art map
+    app.world_mut().get_mut::<DoGHatching>(camera).unwrap().mode = HatchingMode::Layers;
+    app.update();
+    assert!(!app.world().entity(camera).contains::<TonalArtMapTones>());
+}
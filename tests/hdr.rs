@@ -0,0 +1,26 @@
+//! Checks that the blend keeps the colors above 1 of a hdr camera for the tonemapping.
+
+use bevy_dog::{
+    cpu,
+    settings::{DoGSettings, PassesSettings},
+};
+use image::Rgba32FImage;
+
+/// A flat image of `value` in every color channel, which has no edges.
+fn flat(value: f32) -> Rgba32FImage {
+    Rgba32FImage::from_pixel(32, 32, image::Rgba([value, value, value, 1.0]))
+}
+
+#[test]
+fn bright_colors_pass_through_the_blend() {
+    for (preset, settings) in DoGSettings::PRESETS {
+        let settings = DoGSettings {
+            blend_strength: 0.0,
+            ..settings
+        };
+        let output = cpu::process_linear(&flat(4.0), &settings, &PassesSettings::default());
+        for pixel in output.pixels() {
+            assert_eq!(pixel.0, [4.0, 4.0, 4.0, 1.0], "{preset}");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Checks the Rust code the inspector copies, the window itself needs a renderer.
+#![cfg(feature = "egui")]
+
+use bevy_dog::{
+    inspector::settings_as_rust,
+    settings::{DoGSettings, GaussianBackend, PassesSettings},
+};
+
+#[test]
+fn rust_code_lists_the_changed_fields() {
+    let passes = PassesSettings {
+        fdog: true,
+        backend: GaussianBackend::Compute,
+        ..PassesSettings::default()
+    };
+
+    assert_eq!(
+        settings_as_rust(&DoGSettings::OUTLINE_DITHER, &passes),
+        "\
+(
+    DoGSettings {
+        thresholding: Thresholding::Tanh,
+        k: 0.5,
+        tau: 32.0,
+        phi: 0.8,
+        min_color: Vec3::new(0.5, 0.4, 0.4),
+        max_color: Vec3::new(0.8, 1.0, 0.9),
+        thresholds: Vec4::new(12.0, 6.0, 3.0, 0.5),
+        ..default()
+    },
+    PassesSettings {
+        fdog: true,
+        backend: GaussianBackend::Compute,
+        ..default()
+    },
+)"
+    );
+}
+
+#[test]
+fn rust_code_of_the_defaults_has_no_fields() {
+    assert_eq!(
+        settings_as_rust(&DoGSettings::default(), &PassesSettings::default()),
+        "\
+(
+    DoGSettings {
+        ..default()
+    },
+    PassesSettings {
+        ..default()
+    },
+)"
+    );
+}
@@ -0,0 +1,254 @@
+//! Loads the presets in `assets/presets` and checks how [`DoGPresetHandle`] and
+//! [`DoGPresetName`] apply them.
+
+mod common;
+
+use bevy::{prelude::*, reflect::TypeRegistry};
+use bevy_dog::{
+    preset::{DoGPreset, DoGPresetError, DoGPresetHandle, DoGPresetName, DoGPresets},
+    settings::{DoGSettings, GaussianBackend, PassesSettings, Thresholding},
+};
+use common::app;
+
+fn registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::default();
+    registry.register::<DoGPreset>();
+    registry
+}
+
+fn assert_settings_eq(actual: &DoGSettings, expected: &DoGSettings) {
+    assert_eq!(
+        actual.reflect_partial_eq(expected),
+        Some(true),
+        "{actual:#?}\nis not\n{expected:#?}"
+    );
+}
+
+#[test]
+fn presets_match_the_constants() {
+    let assets = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/presets");
+    for (file, settings) in [
+        ("outline.dog.ron", DoGSettings::OUTLINE),
+        ("crosshatch.dog.ron", DoGSettings::CROSSHATCH),
+    ] {
+        let source = std::fs::read_to_string(assets.join(file)).unwrap();
+        let preset = DoGPreset::from_ron(&source, &registry()).unwrap();
+        assert_settings_eq(&preset.settings, &settings);
+    }
+}
+
+#[test]
+fn missing_fields_keep_their_defaults() {
+    let preset = DoGPreset::from_ron(
+        "(settings: (thresholding: Tanh, tau: 3.0), passes: (backend: Compute))",
+        &registry(),
+    )
+    .unwrap();
+
+    assert_settings_eq(
+        &preset.settings,
+        &DoGSettings {
+            thresholding: Thresholding::Tanh,
+            tau: 3.0,
+            ..DoGSettings::default()
+        },
+    );
+    assert_eq!(preset.passes.backend, GaussianBackend::Compute);
+    assert_eq!(
+        preset.passes.resolution_scale,
+        PassesSettings::default().resolution_scale
+    );
+}
+
+#[test]
+fn presets_round_trip_through_ron() {
+    let preset = DoGPreset {
+        settings: DoGSettings::CROSSHATCH,
+        passes: PassesSettings {
+            fdog: true,
+            resolution_scale: 0.5,
+            ..default()
+        },
+    };
+    let ron = preset.to_ron(&registry()).unwrap();
+    let parsed = DoGPreset::from_ron(&ron, &registry()).unwrap();
+
+    assert_eq!(parsed.reflect_partial_eq(&preset), Some(true), "{ron}");
+}
+
+#[test]
+fn unknown_fields_are_errors() {
+    let error = DoGPreset::from_ron("(settings: (sigma: 1.0))", &registry()).unwrap_err();
+    assert!(matches!(error, DoGPresetError::Ron(_)), "{error}");
+}
+
+#[test]
+fn preset_is_applied_when_loaded_and_modified() {
+    let mut app = app();
+    let handle = app
+        .world()
+        .resource::<AssetServer>()
+        .load::<DoGPreset>("presets/outline.dog.ron");
+    let camera = app.world_mut().spawn(DoGPresetHandle(handle.clone())).id();
+
+    for _ in 0..1000 {
+        app.update();
+        if app.world().get::<DoGSettings>(camera).is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    let settings = app
+        .world()
+        .get::<DoGSettings>(camera)
+        .expect("the preset didn't load");
+    assert_settings_eq(settings, &DoGSettings::OUTLINE);
+    assert!(app.world().get::<PassesSettings>(camera).is_some());
+
+    // this is what a hot reload of the file does
+    app.world_mut()
+        .resource_mut::<Assets<DoGPreset>>()
+        .get_mut(&handle)
+        .unwrap()
+        .settings
+        .tau = 7.0;
+    app.update();
+    assert_eq!(app.world().get::<DoGSettings>(camera).unwrap().tau, 7.0);
+}
+
+#[test]
+fn changing_the_handle_applies_the_new_preset() {
+    let mut app = app();
+    let mut presets = app.world_mut().resource_mut::<Assets<DoGPreset>>();
+    let outline = presets.add(DoGPreset {
+        settings: DoGSettings::OUTLINE,
+        ..default()
+    });
+    let crosshatch = presets.add(DoGPreset {
+        settings: DoGSettings::CROSSHATCH,
+        ..default()
+    });
+
+    let camera = app.world_mut().spawn(DoGPresetHandle(outline)).id();
+    app.update();
+    assert_settings_eq(
+        app.world().get::<DoGSettings>(camera).unwrap(),
+        &DoGSettings::OUTLINE,
+    );
+
+    // settings changed in between stay until the preset changes
+    app.world_mut()
+        .get_mut::<DoGSettings>(camera)
+        .unwrap()
+        .sigma_c = 4.0;
+    app.update();
+    assert_eq!(app.world().get::<DoGSettings>(camera).unwrap().sigma_c, 4.0);
+
+    app.world_mut()
+        .get_mut::<DoGPresetHandle>(camera)
+        .unwrap()
+        .0 = crosshatch;
+    app.update();
+    assert_settings_eq(
+        app.world().get::<DoGSettings>(camera).unwrap(),
+        &DoGSettings::CROSSHATCH,
+    );
+}
+
+#[test]
+fn registry_starts_with_the_built_in_presets() {
+    let app = app();
+    let presets = app.world().resource::<DoGPresets>();
+    let assets = app.world().resource::<Assets<DoGPreset>>();
+
+    assert_eq!(
+        presets.names().collect::<Vec<_>>(),
+        ["default", "outline", "outline_dither", "crosshatch"]
+    );
+    for (name, settings) in DoGSettings::PRESETS {
+        let preset = assets.get(presets.get(name).unwrap()).unwrap();
+        assert_settings_eq(&preset.settings, &settings);
+    }
+}
+
+#[test]
+fn registering_a_name_again_replaces_the_preset_in_place() {
+    let mut app = app();
+    let sketch = app
+        .world_mut()
+        .resource_mut::<Assets<DoGPreset>>()
+        .add(DoGPreset::default());
+    let mut presets = app.world_mut().resource_mut::<DoGPresets>();
+
+    assert!(presets.insert("sketch", sketch.clone()).is_none());
+    let outline = presets.insert("outline", sketch.clone()).unwrap();
+    assert_ne!(outline, sketch);
+    assert_eq!(presets.get("outline"), Some(&sketch));
+    assert_eq!(
+        presets.names().collect::<Vec<_>>(),
+        [
+            "default",
+            "outline",
+            "outline_dither",
+            "crosshatch",
+            "sketch"
+        ]
+    );
+
+    assert_eq!(presets.remove("sketch"), Some(sketch));
+    assert!(presets.get("sketch").is_none());
+}
+
+#[test]
+fn preset_name_switches_the_camera() {
+    let mut app = app();
+    let camera = app.world_mut().spawn(DoGPresetName::new("outline")).id();
+    app.update();
+    assert_settings_eq(
+        app.world().get::<DoGSettings>(camera).unwrap(),
+        &DoGSettings::OUTLINE,
+    );
+
+    app.world_mut()
+        .entity_mut(camera)
+        .insert(DoGPresetName::new("crosshatch"));
+    app.update();
+    assert_settings_eq(
+        app.world().get::<DoGSettings>(camera).unwrap(),
+        &DoGSettings::CROSSHATCH,
+    );
+
+    // an unknown name keeps the current preset
+    app.world_mut()
+        .entity_mut(camera)
+        .insert(DoGPresetName::new("missing"));
+    app.update();
+    assert_settings_eq(
+        app.world().get::<DoGSettings>(camera).unwrap(),
+        &DoGSettings::CROSSHATCH,
+    );
+}
+
+#[test]
+fn preset_name_resolves_once_it_is_registered() {
+    let mut app = app();
+    let camera = app.world_mut().spawn(DoGPresetName::new("sketch")).id();
+    app.update();
+    assert!(app.world().get::<DoGSettings>(camera).is_none());
+
+    let sketch = app
+        .world_mut()
+        .resource_mut::<Assets<DoGPreset>>()
+        .add(DoGPreset {
+            settings: DoGSettings {
+                sigma_e: 3.0,
+                ..DoGSettings::OUTLINE
+            },
+            ..default()
+        });
+    app.world_mut()
+        .resource_mut::<DoGPresets>()
+        .insert("sketch", sketch);
+    app.update();
+    assert_eq!(app.world().get::<DoGSettings>(camera).unwrap().sigma_e, 3.0);
+}
@@ -0,0 +1,47 @@
+//! Checks that the lab pass averages the source when it runs at a reduced resolution, so thin
+//! lines don't depend on where they fall in the pixels a texel covers.
+
+use bevy_dog::{
+    cpu,
+    settings::{DoGSettings, PassesSettings},
+};
+use image::RgbaImage;
+
+/// The darkest lightness of the lab pass for a one pixel wide black line at `column`.
+fn darkest_lightness(column: u32, resolution_scale: f32) -> f32 {
+    let image = RgbaImage::from_fn(64, 64, |x, _| {
+        if x == column {
+            image::Rgba([0, 0, 0, 255])
+        } else {
+            image::Rgba([255, 255, 255, 255])
+        }
+    });
+    let passes = PassesSettings {
+        resolution_scale,
+        ..PassesSettings::default()
+    };
+    let (_, intermediates) =
+        cpu::process_with_intermediates(&image, &DoGSettings::default(), &passes);
+    intermediates
+        .lab
+        .pixels()
+        .map(|pixel| pixel.0[0])
+        .fold(f32::MAX, f32::min)
+}
+
+#[test]
+fn thin_lines_keep_their_weight_when_downscaled() {
+    for resolution_scale in [0.5, 0.25] {
+        // every column of the pixels a texel covers
+        let lightness: Vec<_> = (32..32 + (1.0 / resolution_scale) as u32)
+            .map(|column| darkest_lightness(column, resolution_scale))
+            .collect();
+        for value in &lightness {
+            assert!(
+                (value - lightness[0]).abs() < 1e-4,
+                "{lightness:?} at {resolution_scale}"
+            );
+            assert!(*value < 0.99, "the line vanished at {resolution_scale}");
+        }
+    }
+}
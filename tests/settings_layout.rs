@@ -0,0 +1,192 @@
+//! Checks the `DoGSettings` struct of `assets/shaders/settings.wgsl` against the layout encase
+//! writes for `DoGSettingsUniform`, so the shaders read every field where the Rust side puts it.
+
+use bevy::{
+    math::{Vec2, Vec3, Vec4},
+    render::render_resource::{encase::UniformBuffer, ShaderType},
+};
+use bevy_dog::settings::{BlendMode, DoGSettings, DoGSettingsUniform, Thresholding};
+use naga::{ScalarKind, TypeInner, VectorSize};
+
+const SETTINGS_SHADER: &str = include_str!("../assets/shaders/settings.wgsl");
+
+/// Settings where every component has a value no other component has, as far as the types
+/// allow, so a field read at the wrong offset or with the wrong type shows up as a mismatch.
+fn sentinel_settings() -> DoGSettings {
+    DoGSettings {
+        thresholding: Thresholding::SmoothQuantization,
+        blend_mode: BlendMode::TwoPointInterpolate,
+        invert: true,
+        calc_diff_before_convolution: false,
+        sigma_c: 11.0,
+        sigma_e: 12.0,
+        sigma_m: 13.0,
+        sigma_a: 14.0,
+        quantizer_step: 15.0,
+        k: 16.0,
+        tau: 17.0,
+        phi: 18.0,
+        blend_strength: 19.0,
+        dog_strength: 20.0,
+        line_conv_step_sizes: Vec2::new(21.0, 22.0),
+        edge_smooth_step_sizes: Vec2::new(23.0, 24.0),
+        min_color: Vec3::new(25.0, 26.0, 27.0),
+        max_color: Vec3::new(28.0, 29.0, 30.0),
+        enable_hatch: true,
+        enable_layers: Vec4::new(31.0, 32.0, 33.0, 34.0),
+        hatch_resolutions: Vec4::new(35.0, 36.0, 37.0, 38.0),
+        hatch_rotations: Vec4::new(39.0, 40.0, 41.0, 42.0),
+        world_hatch_scale: 63.0,
+        procedural_hatch: true,
+        hatch_spacings: Vec4::new(47.0, 48.0, 49.0, 50.0),
+        hatch_thicknesses: Vec4::new(51.0, 52.0, 53.0, 54.0),
+        hatch_jitters: Vec4::new(55.0, 56.0, 57.0, 58.0),
+        hatch_wobbles: Vec4::new(59.0, 60.0, 61.0, 62.0),
+        thresholds: Vec4::new(43.0, 44.0, 45.0, 46.0),
+    }
+}
+
+/// The values the shader should read for [`sentinel_settings`], in declaration order.
+fn expected_fields() -> Vec<(&'static str, Vec<f64>)> {
+    vec![
+        ("thresholding", vec![3.0]),
+        ("blend_mode", vec![2.0]),
+        ("invert", vec![1.0]),
+        ("calc_diff_before_convolution", vec![0.0]),
+        ("sigma_c", vec![11.0]),
+        ("sigma_e", vec![12.0]),
+        ("sigma_m", vec![13.0]),
+        ("sigma_a", vec![14.0]),
+        ("quantizer_step", vec![15.0]),
+        ("k", vec![16.0]),
+        ("tau", vec![17.0]),
+        ("phi", vec![18.0]),
+        ("blend_strength", vec![19.0]),
+        ("dog_strength", vec![20.0]),
+        ("line_conv_step_sizes", vec![21.0, 22.0]),
+        ("edge_smooth_step_sizes", vec![23.0, 24.0]),
+        ("min_color", vec![25.0, 26.0, 27.0]),
+        ("max_color", vec![28.0, 29.0, 30.0]),
+        ("enable_hatch", vec![1.0]),
+        ("enable_layers", vec![31.0, 32.0, 33.0, 34.0]),
+        ("hatch_resolutions", vec![35.0, 36.0, 37.0, 38.0]),
+        ("hatch_rotations", vec![39.0, 40.0, 41.0, 42.0]),
+        ("world_hatch_scale", vec![63.0]),
+        ("procedural_hatch", vec![1.0]),
+        ("hatch_spacings", vec![47.0, 48.0, 49.0, 50.0]),
+        ("hatch_thicknesses", vec![51.0, 52.0, 53.0, 54.0]),
+        ("hatch_jitters", vec![55.0, 56.0, 57.0, 58.0]),
+        ("hatch_wobbles", vec![59.0, 60.0, 61.0, 62.0]),
+        ("thresholds", vec![43.0, 44.0, 45.0, 46.0]),
+        ("scale_factor", vec![1.0]),
+    ]
+}
+
+fn parse_settings_module() -> naga::Module {
+    // naga doesn't know the naga_oil directives
+    let source: String = SETTINGS_SHADER
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    naga::front::wgsl::parse_str(&source).expect("settings.wgsl should parse")
+}
+
+/// Reads the components of a scalar or vector member the way the shader would.
+fn read_member(bytes: &[u8], offset: usize, inner: &TypeInner) -> Vec<f64> {
+    let (scalar, count) = match *inner {
+        TypeInner::Scalar(scalar) => (scalar, 1),
+        TypeInner::Vector { size, scalar } => (
+            scalar,
+            match size {
+                VectorSize::Bi => 2,
+                VectorSize::Tri => 3,
+                VectorSize::Quad => 4,
+            },
+        ),
+        ref other => panic!("unexpected member type {other:?}"),
+    };
+    assert_eq!(scalar.width, 4, "only 32 bit scalars are expected");
+
+    (0..count)
+        .map(|i| {
+            let start = offset + i * 4;
+            let word: [u8; 4] = bytes[start..start + 4].try_into().unwrap();
+            match scalar.kind {
+                ScalarKind::Sint => i32::from_le_bytes(word) as f64,
+                ScalarKind::Uint => u32::from_le_bytes(word) as f64,
+                ScalarKind::Float => f32::from_le_bytes(word) as f64,
+                kind => panic!("unexpected scalar kind {kind:?}"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn wgsl_settings_match_the_uniform_layout() {
+    let uniform = DoGSettingsUniform::from(&sentinel_settings());
+    let mut buffer = UniformBuffer::new(Vec::<u8>::new());
+    buffer.write(&uniform).unwrap();
+    let bytes = buffer.into_inner();
+
+    let module = parse_settings_module();
+    let (_, settings_type) = module
+        .types
+        .iter()
+        .find(|(_, ty)| ty.name.as_deref() == Some("DoGSettings"))
+        .expect("settings.wgsl should declare DoGSettings");
+    let TypeInner::Struct { members, span } = &settings_type.inner else {
+        panic!("DoGSettings should be a struct");
+    };
+
+    assert_eq!(
+        *span as u64,
+        DoGSettingsUniform::min_size().get(),
+        "the WGSL struct and DoGSettingsUniform differ in size"
+    );
+
+    let expected = expected_fields();
+    let names: Vec<_> = members.iter().map(|m| m.name.as_deref().unwrap()).collect();
+    let expected_names: Vec<_> = expected.iter().map(|(name, _)| *name).collect();
+    assert_eq!(
+        names, expected_names,
+        "the WGSL struct has different fields"
+    );
+
+    for (member, (name, values)) in members.iter().zip(expected) {
+        let read = read_member(
+            &bytes,
+            member.offset as usize,
+            &module.types[member.ty].inner,
+        );
+        assert_eq!(
+            read, values,
+            "`{name}` at offset {} reads different values in the shader",
+            member.offset
+        );
+    }
+}
+
+/// `dog.wgsl`, `fdog.wgsl` and `blend.wgsl` compare the enums against literals.
+#[test]
+fn enum_values_match_the_shaders() {
+    assert_eq!(
+        [
+            Thresholding::NoThreshold,
+            Thresholding::Tanh,
+            Thresholding::Quantization,
+            Thresholding::SmoothQuantization,
+        ]
+        .map(|thresholding| thresholding as i32),
+        [0, 1, 2, 3]
+    );
+    assert_eq!(
+        [
+            BlendMode::NoBlend,
+            BlendMode::Interpolate,
+            BlendMode::TwoPointInterpolate,
+        ]
+        .map(|blend_mode| blend_mode as i32),
+        [0, 1, 2]
+    );
+}
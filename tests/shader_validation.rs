@@ -0,0 +1,221 @@
+//! Composes every shader variant the pipelines in `pipeline.rs` specialize, the same way Bevy's
+//! `PipelineCache` does, and validates the result with naga. This needs no GPU, so shader errors
+//! show up in `cargo test` instead of when a camera with the effect is first rendered.
+
+use bevy::{
+    core_pipeline::CorePipelinePlugin,
+    prelude::*,
+    render::{
+        render_resource::{ShaderDefVal, ShaderImport},
+        view::ViewPlugin,
+    },
+};
+use bevy_dog::{
+    pipeline::blend_shader_defs,
+    plugin::{
+        DoGPlugin, AA_SHADER_HANDLE, BLEND_SHADER_HANDLE, DOG_SHADER_HANDLE, FDOG_SHADER_HANDLE,
+        RGB2LAB_SHADER_HANDLE, TFM_SHADER_HANDLE,
+    },
+};
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    ShaderStage,
+};
+use naga_oil::compose::{Composer, NagaModuleDescriptor, ShaderDefValue};
+
+/// One specialization of a shader, as the pipeline descriptors in `pipeline.rs` request it.
+struct Variant {
+    shader: Handle<Shader>,
+    entry_point: &'static str,
+    stage: ShaderStage,
+    shader_defs: Vec<ShaderDefVal>,
+}
+
+fn fragment(shader: Handle<Shader>, entry_point: &'static str, shader_defs: &[&str]) -> Variant {
+    Variant {
+        shader,
+        entry_point,
+        stage: ShaderStage::Fragment,
+        shader_defs: shader_defs.iter().map(|def| (*def).into()).collect(),
+    }
+}
+
+fn compute(shader: Handle<Shader>, entry_point: &'static str, shader_defs: &[&str]) -> Variant {
+    Variant {
+        stage: ShaderStage::Compute,
+        ..fragment(shader, entry_point, shader_defs)
+    }
+}
+
+/// Every entry point and shader def combination of the render and compute pipelines.
+fn variants() -> Vec<Variant> {
+    let mut variants = vec![
+        // RGB2LABPipeline
+        fragment(RGB2LAB_SHADER_HANDLE, "fragment", &[]),
+        // TFMPipeline, one per TFMPass
+        fragment(TFM_SHADER_HANDLE, "calculate_eigenvector", &["EIGENVECTOR"]),
+        fragment(TFM_SHADER_HANDLE, "vertical_blur_pass", &["VERTICAL"]),
+        fragment(TFM_SHADER_HANDLE, "horizontal_blur_pass", &["HORIZONTAL"]),
+        // FDoGPipeline
+        fragment(FDOG_SHADER_HANDLE, "fdog_blur_pass", &[]),
+        fragment(FDOG_SHADER_HANDLE, "fdog_blur_and_difference", &[]),
+        // DoGPipeline
+        fragment(DOG_SHADER_HANDLE, "first_gaussian_blur_pass", &[]),
+        fragment(DOG_SHADER_HANDLE, "second_gaussian_blur_pass", &[]),
+        // AntiAlliasingPipeline
+        fragment(AA_SHADER_HANDLE, "fragment", &[]),
+        // GaussianComputePipeline, one per GaussianComputePipelineKeys
+        compute(TFM_SHADER_HANDLE, "horizontal_blur_compute", &["COMPUTE"]),
+        compute(TFM_SHADER_HANDLE, "vertical_blur_compute", &["COMPUTE"]),
+        compute(FDOG_SHADER_HANDLE, "fdog_blur_compute", &["COMPUTE"]),
+        compute(
+            DOG_SHADER_HANDLE,
+            "first_gaussian_blur_compute",
+            &["COMPUTE"],
+        ),
+        compute(
+            DOG_SHADER_HANDLE,
+            "second_gaussian_blur_compute",
+            &["COMPUTE", "MASK_OUTPUT"],
+        ),
+    ];
+
+    // BlendPipeline, for every combination of the flags of its keys
+    let mut blend_variants = Vec::new();
+    for flags in 0..16 {
+        let shader_defs = blend_shader_defs(
+            flags & 1 != 0,
+            flags & 2 != 0,
+            flags & 4 != 0,
+            flags & 8 != 0,
+        );
+        if !blend_variants.contains(&shader_defs) {
+            blend_variants.push(shader_defs);
+        }
+    }
+    variants.extend(blend_variants.into_iter().map(|shader_defs| Variant {
+        shader_defs,
+        ..fragment(BLEND_SHADER_HANDLE, "fragment", &[])
+    }));
+    variants
+}
+
+/// The defs the `PipelineCache` adds on top of the ones of the pipeline on WebGL2, where only
+/// the fragment passes run.
+const WEBGL2_SHADER_DEFS: &[&str] = &[
+    "NO_ARRAY_TEXTURES_SUPPORT",
+    "NO_CUBE_ARRAY_TEXTURES_SUPPORT",
+    "SIXTEEN_BYTE_ALIGNMENT",
+];
+
+/// Registers the shaders of the plugin and of the Bevy modules they import, without a renderer.
+fn shader_assets() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Shader>()
+        .init_asset::<Image>()
+        .add_plugins((ViewPlugin, CorePipelinePlugin, DoGPlugin::default()));
+    app
+}
+
+/// Adds the module of `import` and everything it imports, like the `PipelineCache` does.
+fn add_import(composer: &mut Composer, shaders: &Assets<Shader>, import: &ShaderImport) {
+    if composer.contains_module(&import.module_name()) {
+        return;
+    }
+    let (_, shader) = shaders
+        .iter()
+        .find(|(_, shader)| shader.import_path() == import)
+        .unwrap_or_else(|| panic!("no shader provides the import {import:?}"));
+    for import in shader.imports() {
+        add_import(composer, shaders, import);
+    }
+    if let Err(error) = composer.add_composable_module(shader.into()) {
+        panic!("{}", error.emit_to_string(composer));
+    }
+}
+
+fn validate(
+    shaders: &Assets<Shader>,
+    variant: &Variant,
+    extra_shader_defs: &[&str],
+) -> Result<(), String> {
+    let shader = shaders.get(&variant.shader).unwrap();
+    let mut composer = Composer::default();
+    for import in shader.imports() {
+        add_import(&mut composer, shaders, import);
+    }
+
+    let shader_defs = variant
+        .shader_defs
+        .iter()
+        .cloned()
+        .chain(extra_shader_defs.iter().map(|def| ShaderDefVal::from(*def)))
+        .chain([ShaderDefVal::UInt(
+            "AVAILABLE_STORAGE_BUFFER_BINDINGS".into(),
+            8,
+        )])
+        .map(|def| match def {
+            ShaderDefVal::Bool(key, value) => (key, ShaderDefValue::Bool(value)),
+            ShaderDefVal::Int(key, value) => (key, ShaderDefValue::Int(value)),
+            ShaderDefVal::UInt(key, value) => (key, ShaderDefValue::UInt(value)),
+        })
+        .collect();
+
+    let module = composer
+        .make_naga_module(NagaModuleDescriptor {
+            shader_defs,
+            ..shader.into()
+        })
+        .map_err(|error| error.emit_to_string(&composer))?;
+
+    if !module
+        .entry_points
+        .iter()
+        .any(|entry| entry.name == variant.entry_point && entry.stage == variant.stage)
+    {
+        return Err(format!(
+            "there is no {:?} entry point `{}`",
+            variant.stage, variant.entry_point
+        ));
+    }
+
+    Validator::new(ValidationFlags::all(), Capabilities::default())
+        .validate(&module)
+        .map(|_| ())
+        .map_err(|error| format!("{error:?}"))
+}
+
+fn validate_all(extra_shader_defs: &[&str], stages: &[ShaderStage]) {
+    let app = shader_assets();
+    let shaders = app.world().resource::<Assets<Shader>>();
+
+    let failures: Vec<_> = variants()
+        .iter()
+        .filter(|variant| stages.contains(&variant.stage))
+        .filter_map(|variant| {
+            validate(shaders, variant, extra_shader_defs)
+                .err()
+                .map(|error| {
+                    format!(
+                        "{} {:?} with {:?}:\n{error}",
+                        shaders.get(&variant.shader).unwrap().path,
+                        variant.entry_point,
+                        variant.shader_defs
+                    )
+                })
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn every_shader_variant_validates() {
+    validate_all(&[], &[ShaderStage::Fragment, ShaderStage::Compute]);
+}
+
+#[test]
+fn fragment_passes_validate_on_webgl2() {
+    validate_all(WEBGL2_SHADER_DEFS, &[ShaderStage::Fragment]);
+}
@@ -0,0 +1,120 @@
+//! Checks how [`DoGTransition`] interpolates between settings and how it drives a camera.
+
+mod common;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_dog::{
+    settings::{DoGSettings, Thresholding},
+    transition::DoGTransition,
+};
+use common::app;
+use std::time::Duration;
+
+fn at(transition: &DoGTransition, progress: f32) -> DoGTransition {
+    DoGTransition {
+        elapsed: transition.duration.mul_f32(progress),
+        ..transition.clone()
+    }
+}
+
+#[test]
+fn continuous_fields_follow_the_ease() {
+    let transition = DoGTransition::new(DoGSettings::CROSSHATCH, Duration::from_secs(1))
+        .with_ease(EaseFunction::Linear);
+    let from = DoGSettings::OUTLINE;
+
+    let start = at(&transition, 0.0).sample(&from);
+    assert_eq!(start.tau, from.tau);
+    let middle = at(&transition, 0.25).sample(&from);
+    assert_eq!(middle.tau, from.tau.lerp(DoGSettings::CROSSHATCH.tau, 0.25));
+    assert_eq!(
+        middle.max_color,
+        from.max_color.lerp(DoGSettings::CROSSHATCH.max_color, 0.25)
+    );
+    let end = at(&transition, 1.0).sample(&from);
+    assert_eq!(end.tau, DoGSettings::CROSSHATCH.tau);
+
+    let eased = at(&transition.with_ease(EaseFunction::QuadraticIn), 0.5).sample(&from);
+    assert_eq!(eased.tau, from.tau.lerp(DoGSettings::CROSSHATCH.tau, 0.25));
+}
+
+#[test]
+fn discrete_fields_switch_at_the_switch_point() {
+    let transition =
+        DoGTransition::new(DoGSettings::CROSSHATCH, Duration::from_secs(1)).with_switch_at(0.3);
+    let from = DoGSettings::OUTLINE;
+
+    let before = at(&transition, 0.29).sample(&from);
+    assert_eq!(before.thresholding, Thresholding::NoThreshold);
+    assert!(!before.enable_hatch);
+    assert_eq!(before.enable_layers, from.enable_layers);
+
+    let after = at(&transition, 0.31).sample(&from);
+    assert_eq!(after.thresholding, Thresholding::Tanh);
+    assert!(after.enable_hatch);
+    assert_eq!(after.enable_layers, DoGSettings::CROSSHATCH.enable_layers);
+    // switching doesn't make the continuous fields jump
+    assert!((after.tau - before.tau).abs() < 0.5);
+}
+
+#[test]
+fn procedural_strokes_are_blended() {
+    let from = DoGSettings {
+        procedural_hatch: true,
+        ..DoGSettings::CROSSHATCH
+    };
+    let to = DoGSettings {
+        hatch_spacings: Vec4::splat(40.0),
+        hatch_wobbles: Vec4::splat(0.5),
+        ..from
+    };
+    let transition = DoGTransition::new(to, Duration::from_secs(1)).with_ease(EaseFunction::Linear);
+
+    let middle = at(&transition, 0.5).sample(&from);
+    assert!(middle.procedural_hatch);
+    assert_eq!(
+        middle.hatch_spacings,
+        from.hatch_spacings.lerp(to.hatch_spacings, 0.5)
+    );
+    assert_eq!(
+        middle.hatch_wobbles,
+        from.hatch_wobbles.lerp(to.hatch_wobbles, 0.5)
+    );
+    assert_eq!(middle.hatch_thicknesses, from.hatch_thicknesses);
+}
+
+#[test]
+fn transition_animates_the_camera_and_removes_itself() {
+    let mut app = app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        100,
+    )));
+
+    let camera = app
+        .world_mut()
+        .spawn((
+            DoGSettings::OUTLINE,
+            DoGTransition::new(DoGSettings::CROSSHATCH, Duration::from_secs(1)),
+        ))
+        .id();
+
+    let mut taus = Vec::new();
+    for _ in 0..20 {
+        app.update();
+        taus.push(app.world().get::<DoGSettings>(camera).unwrap().tau);
+        if app.world().get::<DoGTransition>(camera).is_none() {
+            break;
+        }
+    }
+
+    assert!(
+        app.world().get::<DoGTransition>(camera).is_none(),
+        "the transition didn't finish: {taus:?}"
+    );
+    assert!(taus.len() >= 10, "{taus:?}");
+    // tau falls from the outline to the crosshatch value
+    assert!(taus.windows(2).all(|pair| pair[1] <= pair[0]), "{taus:?}");
+    let settings = app.world().get::<DoGSettings>(camera).unwrap();
+    assert_eq!(settings.tau, DoGSettings::CROSSHATCH.tau);
+    assert_eq!(settings.thresholding, Thresholding::Tanh);
+}